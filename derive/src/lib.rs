@@ -0,0 +1,212 @@
+//! Derive macros for [hc_crud_ceps](https://crates.io/crates/hc_crud_ceps)
+//!
+//! These are re-exported by `hc_crud` when the `derive` feature is enabled.
+//!
+
+use proc_macro::TokenStream;
+use proc_macro2::{ Span, TokenStream as TokenStream2 };
+use quote::{ quote, quote_spanned };
+use syn::{
+    parse_macro_input, DeriveInput, Ident, LitInt, LitStr, Path, Token,
+    ext::IdentExt,
+    parse::{ Parse, ParseStream },
+    spanned::Spanned,
+};
+
+
+/// Implement `hc_crud::EntryModel` for a struct that is wrapped by an entry types enum variant
+///
+/// ```ignore
+/// #[hdk_entry_helper]
+/// #[derive(Clone, EntryModel)]
+/// #[entry_model(name = "Post", type = "post", model = "entry", variant = EntryTypes::Post)]
+/// pub struct PostEntry {
+///     pub message: String,
+/// }
+/// ```
+///
+/// The enum type is taken from the `variant` path, so the generated impl is
+/// `EntryModel<EntryTypes>`.  If the variant does not wrap the deriving struct, compilation fails
+/// at the `variant` value.
+///
+/// The optional `version = 2` and `upgrades = path::to_fn` arguments set `EntryModel::version` and
+/// `EntryModel::upgrades` (a `fn() -> Vec<hc_crud::Upgrade<Self>>`).
+#[proc_macro_derive(EntryModel, attributes(entry_model))]
+pub fn derive_entry_model(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand_entry_model( input )
+	.unwrap_or_else( |error| error.to_compile_error() )
+	.into()
+}
+
+
+struct EntryModelArgs {
+    name: LitStr,
+    ctype: LitStr,
+    model: LitStr,
+    variant: Path,
+    version: Option<LitInt>,
+    upgrades: Option<Path>,
+}
+
+impl Parse for EntryModelArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+	let mut name : Option<LitStr> = None;
+	let mut ctype : Option<LitStr> = None;
+	let mut model : Option<LitStr> = None;
+	let mut variant : Option<Path> = None;
+	let mut version : Option<LitInt> = None;
+	let mut upgrades : Option<Path> = None;
+
+	while !input.is_empty() {
+	    // 'type' is a keyword so it cannot be parsed as a plain identifier
+	    let key = Ident::parse_any( input )?;
+	    input.parse::<Token![=]>()?;
+
+	    match key.to_string().as_str() {
+		"name" => name = Some( input.parse()? ),
+		"type" => ctype = Some( input.parse()? ),
+		"model" => model = Some( input.parse()? ),
+		"variant" => variant = Some( input.parse()? ),
+		"version" => version = Some( input.parse()? ),
+		"upgrades" => upgrades = Some( input.parse()? ),
+		other => return Err(syn::Error::new(
+		    key.span(),
+		    format!("Unknown entry_model argument '{}'; expected one of: name, type, model, variant, version, upgrades", other )
+		)),
+	    }
+
+	    if input.is_empty() {
+		break;
+	    }
+	    input.parse::<Token![,]>()?;
+	}
+
+	let missing = |arg: &str| syn::Error::new(
+	    input.span(),
+	    format!("Missing entry_model argument '{}'", arg )
+	);
+
+	Ok(EntryModelArgs {
+	    name: name.ok_or_else( || missing("name") )?,
+	    ctype: ctype.ok_or_else( || missing("type") )?,
+	    model: model.ok_or_else( || missing("model") )?,
+	    variant: variant.ok_or_else( || missing("variant") )?,
+	    version,
+	    upgrades,
+	})
+    }
+}
+
+
+fn expand_entry_model(input: DeriveInput) -> syn::Result<TokenStream2> {
+    if !matches!( input.data, syn::Data::Struct(_) ) {
+	return Err(syn::Error::new(
+	    input.ident.span(),
+	    "EntryModel can only be derived for structs"
+	));
+    }
+
+    let attr = input.attrs.iter()
+	.find( |attr| attr.path.is_ident("entry_model") )
+	.ok_or_else( || syn::Error::new(
+	    Span::call_site(),
+	    "Missing #[entry_model(name = \"..\", type = \"..\", model = \"..\", variant = Enum::Variant)] attribute"
+	))?;
+    let EntryModelArgs { name, ctype, model, variant, version, upgrades } = attr.parse_args()?;
+
+    if variant.segments.len() < 2 {
+	return Err(syn::Error::new(
+	    variant.span(),
+	    "Expected the variant to include its enum type (eg. 'EntryTypes::Post')"
+	));
+    }
+
+    let enum_path = Path {
+	leading_colon: variant.leading_colon,
+	segments: variant.segments.iter()
+	    .take( variant.segments.len() - 1 )
+	    .cloned()
+	    .collect(),
+    };
+
+    let ident = &input.ident;
+    let ( impl_generics, ty_generics, where_clause ) = input.generics.split_for_impl();
+
+    // Spanned on the variant so that a variant wrapping a different struct reports the mismatch
+    // at the attribute instead of inside the generated impl.
+    let to_input = quote_spanned! {variant.span()=>
+	#variant( ::core::clone::Clone::clone( self ) )
+    };
+
+    let version = version.map( |version| quote! {
+	fn version() -> u32 { #version }
+    });
+    let upgrades = upgrades.map( |upgrades| quote! {
+	fn upgrades() -> ::std::vec::Vec<::hc_crud::Upgrade<Self>> { #upgrades() }
+    });
+
+    Ok(quote! {
+	impl #impl_generics ::hc_crud::EntryModel<#enum_path> for #ident #ty_generics #where_clause {
+	    fn name() -> &'static str { #name }
+	    fn get_type(&self) -> ::hc_crud::EntityType {
+		::hc_crud::EntityType::new( #ctype, #model )
+	    }
+	    fn to_input(&self) -> #enum_path {
+		#to_input
+	    }
+	    #version
+	    #upgrades
+	}
+    })
+}
+
+
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_entry_model_test() {
+	let input : DeriveInput = syn::parse_quote! {
+	    #[entry_model(name = "Post", type = "post", model = "entry", variant = EntryTypes::Post)]
+	    pub struct PostEntry {
+		pub message: String,
+	    }
+	};
+
+	let output = expand_entry_model( input ).unwrap().to_string();
+
+	assert!( output.contains("EntryModel < EntryTypes > for PostEntry") );
+	assert!( output.contains("\"Post\"") );
+	assert!( output.contains("EntityType :: new (\"post\" , \"entry\")") );
+	assert!( output.contains("EntryTypes :: Post (") );
+	assert!( !output.contains("fn version") );
+
+	let input : DeriveInput = syn::parse_quote! {
+	    #[entry_model(name = "Post", type = "post", model = "entry", variant = EntryTypes::Post, version = 2, upgrades = post_upgrades)]
+	    pub struct PostEntry {}
+	};
+
+	let output = expand_entry_model( input ).unwrap().to_string();
+
+	assert!( output.contains("fn version () -> u32 { 2 }") );
+	assert!( output.contains("{ post_upgrades () }") );
+
+	let input : DeriveInput = syn::parse_quote! {
+	    #[entry_model(name = "Post", type = "post", model = "entry", variant = Post)]
+	    pub struct PostEntry {}
+	};
+
+	assert!( expand_entry_model( input ).is_err() );
+
+	let input : DeriveInput = syn::parse_quote! {
+	    #[entry_model(name = "Post", type = "post", variant = EntryTypes::Post)]
+	    pub struct PostEntry {}
+	};
+
+	assert!( expand_entry_model( input ).is_err() );
+    }
+}
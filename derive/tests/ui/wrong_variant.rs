@@ -0,0 +1,24 @@
+use hdk::prelude::*;
+use hc_crud::EntryModel;
+
+#[hdk_entry_helper]
+#[derive(Clone, EntryModel)]
+#[entry_model(name = "Post", type = "post", model = "entry", variant = EntryTypes::Comment)]
+pub struct PostEntry {
+    pub message: String,
+}
+
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct CommentEntry {
+    pub message: String,
+}
+
+#[hdk_entry_defs(skip_hdk_extern = true)]
+#[unit_enum(UnitEntryTypes)]
+pub enum EntryTypes {
+    Post(PostEntry),
+    Comment(CommentEntry),
+}
+
+fn main() {}
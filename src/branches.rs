@@ -0,0 +1,130 @@
+use hdk::prelude::*;
+
+
+/// Decides which update to follow when an action has been updated more than once
+///
+/// `origin` is the action the update chain is being followed from and `updates` is never empty.
+pub trait BranchResolver {
+    fn resolve(&self, origin: &Action, updates: Vec<SignedActionHashed>) -> Option<SignedActionHashed>;
+}
+
+
+/// Follow the update with the earliest timestamp (the default)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EarliestUpdate;
+
+impl BranchResolver for EarliestUpdate {
+    fn resolve(&self, _: &Action, updates: Vec<SignedActionHashed>) -> Option<SignedActionHashed> {
+	crate::find_earliest_action( updates )
+    }
+}
+
+
+/// Follow the update with the latest timestamp
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LatestUpdate;
+
+impl BranchResolver for LatestUpdate {
+    fn resolve(&self, _: &Action, updates: Vec<SignedActionHashed>) -> Option<SignedActionHashed> {
+	updates.into_iter()
+	    .max_by( |a, b| sort_key( a ).cmp( &sort_key( b ) ) )
+    }
+}
+
+
+/// Follow the earliest update and break timestamp ties using the action hash so that every agent
+/// resolves the same branch
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EarliestUpdateByHash;
+
+impl BranchResolver for EarliestUpdateByHash {
+    fn resolve(&self, _: &Action, updates: Vec<SignedActionHashed>) -> Option<SignedActionHashed> {
+	updates.into_iter()
+	    .min_by( |a, b| sort_key( a ).cmp( &sort_key( b ) ) )
+    }
+}
+
+
+/// Follow the earliest update made by the origin's author; falls back to the earliest update by
+/// anyone else
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OriginalAuthorUpdate;
+
+impl BranchResolver for OriginalAuthorUpdate {
+    fn resolve(&self, origin: &Action, updates: Vec<SignedActionHashed>) -> Option<SignedActionHashed> {
+	let (by_author, others) : (Vec<_>, Vec<_>) = updates.into_iter()
+	    .partition( |sh| sh.action().author() == origin.author() );
+
+	EarliestUpdateByHash.resolve( origin, by_author )
+	    .or_else( || EarliestUpdateByHash.resolve( origin, others ) )
+    }
+}
+
+
+fn sort_key(sh: &SignedActionHashed) -> (Timestamp, &ActionHash) {
+    ( sh.action().timestamp(), sh.action_address() )
+}
+
+
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use rand::Rng;
+
+    fn random_bytes() -> Vec<u8> {
+	rand::thread_rng().gen::<[u8; 32]>().to_vec()
+    }
+
+    fn update(author: &AgentPubKey, timestamp: i64) -> SignedActionHashed {
+	let action = Action::Update(Update {
+	    author: author.to_owned(),
+	    timestamp: Timestamp::from_micros( timestamp ),
+	    action_seq: 1,
+	    prev_action: ActionHash::from_raw_32( random_bytes() ),
+	    original_action_address: ActionHash::from_raw_32( random_bytes() ),
+	    original_entry_address: EntryHash::from_raw_32( random_bytes() ),
+	    entry_type: EntryType::AgentPubKey,
+	    entry_hash: EntryHash::from_raw_32( random_bytes() ),
+	    weight: Default::default(),
+	});
+
+	SignedActionHashed::with_presigned(
+	    ActionHashed::from_content_sync( action ),
+	    Signature([0; 64]),
+	)
+    }
+
+    #[test]
+    fn branch_resolver_test() {
+	let alice = AgentPubKey::from_raw_32( random_bytes() );
+	let bobby = AgentPubKey::from_raw_32( random_bytes() );
+	let origin = update( &alice, 0 ).action().to_owned();
+
+	let first = update( &bobby, 1 );
+	let tie = update( &bobby, 1 );
+	let by_alice = update( &alice, 2 );
+	let last = update( &bobby, 3 );
+	let updates = vec![ last.clone(), by_alice.clone(), tie.clone(), first.clone() ];
+
+	let earliest = EarliestUpdate.resolve( &origin, updates.clone() ).unwrap();
+	assert_eq!( earliest.action().timestamp(), Timestamp::from_micros( 1 ) );
+
+	let latest = LatestUpdate.resolve( &origin, updates.clone() ).unwrap();
+	assert_eq!( latest.action_address(), last.action_address() );
+
+	let expected_tie_winner = std::cmp::min( first.action_address(), tie.action_address() );
+	let by_hash = EarliestUpdateByHash.resolve( &origin, updates.clone() ).unwrap();
+	assert_eq!( by_hash.action_address(), expected_tie_winner );
+
+	let reversed = updates.iter().rev().cloned().collect();
+	let by_hash = EarliestUpdateByHash.resolve( &origin, reversed ).unwrap();
+	assert_eq!( by_hash.action_address(), expected_tie_winner );
+
+	let original = OriginalAuthorUpdate.resolve( &origin, updates.clone() ).unwrap();
+	assert_eq!( original.action_address(), by_alice.action_address() );
+
+	let original = OriginalAuthorUpdate.resolve( &origin, vec![ last, first.clone() ] ).unwrap();
+	assert_eq!( original.action_address(), first.action_address() );
+    }
+}
@@ -0,0 +1,230 @@
+//! CRUD functions for entities identified by their Create ActionHash
+//!
+//! Two identical creates share the same EntryHash so the default functions (which use the
+//! EntryHash as the ID) cannot tell them apart.  The functions in this module use the Create
+//! ActionHash as the [`ActionEntity::id`](crate::Entity::id) instead; the update chain and links
+//! work the same way.
+//!
+//! ```ignore
+//! let post = hc_crud::by_action::create_entity( &input )?;
+//! let post : ActionEntity<PostEntry> = hc_crud::by_action::get_entity( &post.id )?;
+//! ```
+
+use std::convert::TryFrom;
+use hdk::prelude::*;
+use crate::entities::{ Entity, ActionEntity, EntryModel };
+use crate::errors::{ UtilsResult, UtilsError };
+use crate::options::{ ReadOptions, UpdateOptions, ReadStrategy };
+use crate::utils::{ to_entry_type, RecordSource };
+
+
+/// Create a new entity identified by its Create action
+pub fn create_entity<T,I,E>(entry: &T) -> UtilsResult<ActionEntity<T>>
+where
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a I, Error = WasmError>,
+    EntryVisibility: for<'a> From<&'a I>,
+    Entry: TryFrom<I, Error = E>,
+    Entry: TryFrom<T, Error = E>,
+    WasmError: From<E>,
+    T: Clone + EntryModel<I>,
+{
+    let entity = crate::create_entity( entry )?;
+    let id = entity.action.to_owned();
+
+    Ok( with_id( entity, id ) )
+}
+
+/// Get an entity by its Create action
+///
+/// Fails with [`UtilsError::ActionEntityDeleted`] if the entity has been deleted.
+pub fn get_entity<I,ET>(id: &ActionHash) -> UtilsResult<ActionEntity<I>>
+where
+    I: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    Entry: TryFrom<I, Error = WasmError>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    get_entity_with( id, &ReadOptions::default() )
+}
+
+/// Get an entity by its Create action using the given [`ReadOptions`]
+pub fn get_entity_with<I,ET>(id: &ActionHash, options: &ReadOptions) -> UtilsResult<ActionEntity<I>>
+where
+    I: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    Entry: TryFrom<I, Error = WasmError>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    get_entity_from( id, options, &RecordSource::new( options.strategy )? )
+}
+
+fn get_entity_from<I,ET>(id: &ActionHash, options: &ReadOptions, source: &RecordSource) -> UtilsResult<ActionEntity<I>>
+where
+    I: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    let origin = fetch_create_record( id, source )?;
+
+    if let Some(delete_action) = crate::find_delete_from( id, source )? {
+	return Err(UtilsError::ActionEntityDeleted(id.to_owned(), delete_action));
+    }
+
+    let updates = crate::follow_updates_from( id, options, source )?;
+    let latest = updates.last().unwrap();
+    let record = source.get( latest.to_owned() )?
+	.ok_or( UtilsError::ActionNotFoundError(latest.to_owned(), Some("".to_string())) )?;
+
+    crate::to_entity( id, origin.action(), record )
+}
+
+/// Update an entity; see [`update_entity`](crate::update_entity)
+pub fn update_entity<T,I,F,E>(addr: &ActionHash, callback: F) -> UtilsResult<ActionEntity<T>>
+where
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a I, Error = WasmError>,
+    Entry: TryFrom<I, Error = E>,
+    Entry: TryFrom<T, Error = E>,
+    WasmError: From<E>,
+    T: TryFrom<Record, Error = WasmError>,
+    T: Clone + EntryModel<I>,
+    F: FnOnce(T, Record) -> UtilsResult<T>,
+{
+    update_entity_with( addr, &UpdateOptions::default(), callback )
+}
+
+/// Update an entity using the given [`UpdateOptions`]
+pub fn update_entity_with<T,I,F,E>(addr: &ActionHash, options: &UpdateOptions, callback: F) -> UtilsResult<ActionEntity<T>>
+where
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a I, Error = WasmError>,
+    Entry: TryFrom<I, Error = E>,
+    Entry: TryFrom<T, Error = E>,
+    WasmError: From<E>,
+    T: TryFrom<Record, Error = WasmError>,
+    T: Clone + EntryModel<I>,
+    F: FnOnce(T, Record) -> UtilsResult<T>,
+{
+    let (origin_action, entity) = crate::update_chain_with( addr, options, callback )?;
+
+    Ok( with_id( entity, origin_action ) )
+}
+
+/// Delete an entity by its Create action
+pub fn delete_entity<T,ET>(id: &ActionHash) -> UtilsResult<ActionHash>
+where
+    T: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    Entry: TryFrom<T, Error = WasmError>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    let record = fetch_create_record( id, &RecordSource::new( ReadStrategy::Latest )? )?;
+    let _ : T = to_entry_type( record )?;
+
+    Ok( delete_entry( id.to_owned() )? )
+}
+
+/// Get multiple entities for a given base and link tag filter
+///
+/// Links that do not target an ActionHash or that cannot be resolved are skipped.
+pub fn get_entities<T,B,LT,ET>(base: &B, link_type: LT, tag: Option<Vec<u8>>) -> UtilsResult<Vec<ActionEntity<T>>>
+where
+    T: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    B: Clone + Into<AnyLinkableHash>,
+    LT: LinkTypeFilterExt,
+    Entry: TryFrom<T, Error = WasmError>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    get_entities_with( base, link_type, tag, &ReadOptions::default() )
+}
+
+/// Get multiple entities for a given base and link tag filter using the given [`ReadOptions`]
+pub fn get_entities_with<T,B,LT,ET>(base: &B, link_type: LT, tag: Option<Vec<u8>>, options: &ReadOptions) -> UtilsResult<Vec<ActionEntity<T>>>
+where
+    T: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    B: Clone + Into<AnyLinkableHash>,
+    LT: LinkTypeFilterExt,
+    Entry: TryFrom<T, Error = WasmError>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    let links = get_links(
+	base.to_owned(),
+	link_type,
+	tag.map( LinkTag::new )
+    )?;
+    let source = RecordSource::new( options.strategy )?;
+
+    Ok(
+	crate::links_after( links, None ).into_iter()
+	    .filter_map( |link| link.target.into_action_hash() )
+	    .filter_map( |target| get_entity_from( &target, options, &source ).ok() )
+	    .collect()
+    )
+}
+
+
+fn fetch_create_record(id: &ActionHash, source: &RecordSource) -> UtilsResult<Record> {
+    let record = source.get( id.to_owned() )?
+	.ok_or( UtilsError::ActionNotFoundError(id.to_owned(), None) )?;
+
+    match record.action() {
+	Action::Create(_) => Ok( record ),
+	_ => Err(UtilsError::NotOriginEntryError(id.to_owned())),
+    }
+}
+
+fn with_id<T>(entity: Entity<T>, id: ActionHash) -> ActionEntity<T> {
+    Entity {
+	id,
+	action: entity.action,
+	address: entity.address,
+	ctype: entity.ctype,
+	content: entity.content,
+	meta: entity.meta,
+	version: entity.version,
+    }
+}
+
+
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::tests::{ setup, post, comment, PostEntry, CommentEntry, EntryTypes, LinkTypes };
+
+    #[test]
+    fn action_entity_test() {
+	setup();
+
+	let first = create_entity( &post("Same") ).unwrap();
+	let second = create_entity( &post("Same") ).unwrap();
+
+	assert_eq!( first.address, second.address );
+	assert_ne!( first.id, second.id );
+
+	let updated = update_entity( &second.action, |mut current: PostEntry, _| {
+	    current.message = "Changed".to_string();
+	    Ok( current )
+	}).unwrap();
+	assert_eq!( updated.id, second.id );
+
+	let fetched : ActionEntity<PostEntry> = get_entity( &first.id ).unwrap();
+	assert_eq!( fetched.content.message, "Same" );
+
+	let fetched : ActionEntity<PostEntry> = get_entity( &second.id ).unwrap();
+	assert_eq!( fetched.action, updated.action );
+	assert_eq!( fetched.content.message, "Changed" );
+
+	let error = get_entity::<PostEntry,EntryTypes>( &updated.action ).unwrap_err();
+	assert!( matches!( error, UtilsError::NotOriginEntryError(_) ), "{:?}", error );
+
+	let note = create_entity( &comment("Note") ).unwrap();
+	note.link_from( &first.id, LinkTypes::Comment, None ).unwrap();
+	note.link_from( &second.id, LinkTypes::Comment, None ).unwrap();
+
+	let comments : Vec<ActionEntity<CommentEntry>> = get_entities( &first.id, LinkTypes::Comment, None ).unwrap();
+	assert_eq!( comments.len(), 1 );
+	assert_eq!( comments[0].id, note.id );
+
+	let delete_hash = delete_entity::<PostEntry,EntryTypes>( &first.id ).unwrap();
+	let error = get_entity::<PostEntry,EntryTypes>( &first.id ).unwrap_err();
+	assert!( matches!( error, UtilsError::ActionEntityDeleted(_, ref hash) if *hash == delete_hash ), "{:?}", error );
+
+	let fetched : ActionEntity<PostEntry> = get_entity( &second.id ).unwrap();
+	assert_eq!( fetched.content.message, "Changed" );
+    }
+}
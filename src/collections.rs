@@ -0,0 +1,118 @@
+use hdk::prelude::*;
+use crate::entities::Entity;
+use crate::errors::UtilsError;
+
+
+/// An opaque position in a list of links
+///
+/// Links are ordered by their timestamp and then by their create link hash, so a cursor always
+/// points to the same position even if new links are added after it was issued.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkCursor {
+    timestamp: Timestamp,
+    create_link_hash: ActionHash,
+}
+
+impl LinkCursor {
+    /// Create a cursor pointing at the given link
+    pub fn from_link(link: &Link) -> Self {
+	LinkCursor {
+	    timestamp: link.timestamp,
+	    create_link_hash: link.create_link_hash.to_owned(),
+	}
+    }
+
+    fn is_before(&self, link: &Link) -> bool {
+	( &self.timestamp, &self.create_link_hash ) < ( &link.timestamp, &link.create_link_hash )
+    }
+}
+
+
+/// A single page of items and the cursor for fetching the next page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page<T> {
+    /// The items in this page
+    pub items: Vec<T>,
+
+    /// The cursor to pass in for the next page; `None` when there are no more items
+    pub next: Option<LinkCursor>,
+}
+
+
+/// The entities resolved from a list of links along with the links that could not be resolved
+///
+/// Each failure is serialized as the link and the error's [`ErrorPayload`](crate::ErrorPayload).
+#[derive(Debug, Serialize)]
+pub struct EntityCollection<T> {
+    /// The successfully resolved entities
+    pub entities: Vec<Entity<T>>,
+
+    /// The links whose target could not be resolved and the reason why
+    pub failures: Vec<(Link, UtilsError)>,
+}
+
+impl<T> EntityCollection<T> {
+    /// Returns `true` if every link was resolved
+    pub fn is_complete(&self) -> bool {
+	self.failures.is_empty()
+    }
+}
+
+
+/// Sort links by timestamp (and create link hash for ties) and drop the links up to and including
+/// the given cursor
+pub fn links_after(mut links: Vec<Link>, cursor: Option<&LinkCursor>) -> Vec<Link> {
+    links.sort_by( |a, b| {
+	( &a.timestamp, &a.create_link_hash ).cmp( &( &b.timestamp, &b.create_link_hash ) )
+    });
+
+    match cursor {
+	None => links,
+	Some(cursor) => links.into_iter()
+	    .filter( |link| cursor.is_before( link ) )
+	    .collect(),
+    }
+}
+
+
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use rand::Rng;
+
+    fn random_link(timestamp: i64) -> Link {
+	let bytes = rand::thread_rng().gen::<[u8; 32]>();
+
+	Link {
+	    author: holo_hash::AgentPubKey::from_raw_32( bytes.to_vec() ),
+	    target: holo_hash::EntryHash::from_raw_32( bytes.to_vec() ).into(),
+	    timestamp: Timestamp::from_micros( timestamp ),
+	    zome_index: 0.into(),
+	    link_type: 0.into(),
+	    tag: LinkTag::new( vec![] ),
+	    create_link_hash: holo_hash::ActionHash::from_raw_32( bytes.to_vec() ),
+	}
+    }
+
+    #[test]
+    fn links_after_test() {
+	let links = vec![ random_link( 3 ), random_link( 1 ), random_link( 2 ), random_link( 2 ) ];
+
+	let sorted = links_after( links.clone(), None );
+	let timestamps : Vec<i64> = sorted.iter().map( |link| link.timestamp.as_micros() ).collect();
+
+	assert_eq!( timestamps, vec![ 1, 2, 2, 3 ] );
+	assert!( sorted[1].create_link_hash < sorted[2].create_link_hash );
+
+	let cursor = LinkCursor::from_link( &sorted[1] );
+	let mut links = links;
+	links.push( random_link( 0 ) );
+
+	let remaining = links_after( links, Some(&cursor) );
+
+	assert_eq!( remaining.len(), 2 );
+	assert_eq!( remaining[0].create_link_hash, sorted[2].create_link_hash );
+	assert_eq!( remaining[1].create_link_hash, sorted[3].create_link_hash );
+    }
+}
@@ -3,6 +3,11 @@ use hdk::prelude::*;
 use crate::errors::{
     UtilsResult, UtilsError,
 };
+use crate::tags::encode_tag;
+use crate::options::UnlinkOptions;
+use crate::collections::links_after;
+use crate::utils::path_from_collection;
+use crate::versions::Upgrade;
 
 
 /// An Entity categorization format that required the name and model values
@@ -23,6 +28,19 @@ where
     fn name() -> &'static str;
     fn get_type(&self) -> EntityType;
     fn to_input(&self) -> T;
+
+    /// The version of the current content shape
+    fn version() -> u32 {
+	1
+    }
+
+    /// Earlier content shapes that are upgraded when read (newest first)
+    fn upgrades() -> Vec<Upgrade<Self>>
+    where
+	Self: Sized,
+    {
+	Vec::new()
+    }
 }
 
 impl EntityType {
@@ -36,10 +54,13 @@ impl EntityType {
 
 
 /// The context and content of a specific entry
+///
+/// The ID is the original entry's EntryHash unless the entity was loaded through
+/// [`by_action`](crate::by_action), in which case it is the Create ActionHash.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Entity<T> {
-    /// The address of the original created entry
-    pub id: EntryHash,
+pub struct Entity<T, I = EntryHash> {
+    /// The address of the original created entry (or its Create action)
+    pub id: I,
 
     /// The create/update action of the current entry
     pub action: ActionHash,
@@ -53,55 +74,118 @@ pub struct Entity<T> {
 
     /// The entity's current value
     pub content: T,
+
+    /// Who created and last changed the entity and when (when it is known)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<EntityMeta>,
+
+    /// The [`EntryModel::version`] of the content as it was stored (before any upgrade)
+    ///
+    /// This is inferred from the shape the record decodes as and is `None` when more than one shape
+    /// can decode it; see [`Upgrade`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
 }
 
-impl<T> Entity<T> {
+/// The authors and timestamps of an entity's Create action and current create/update action
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntityMeta {
+    /// The author of the Create action
+    pub author: AgentPubKey,
+
+    /// The timestamp of the Create action
+    pub created_at: Timestamp,
+
+    /// The author of the current create/update action
+    pub latest_author: AgentPubKey,
+
+    /// The timestamp of the current create/update action
+    pub updated_at: Timestamp,
+}
+
+impl EntityMeta {
+    pub(crate) fn new(origin: &Action, latest: &Action) -> Self {
+	EntityMeta {
+	    author: origin.author().to_owned(),
+	    created_at: origin.timestamp(),
+	    latest_author: latest.author().to_owned(),
+	    updated_at: latest.timestamp(),
+	}
+    }
+}
+
+/// An entity identified by its Create ActionHash (see [`by_action`](crate::by_action))
+///
+/// The link methods take bases and targets of the same type as the ID, so an `ActionEntity` links
+/// to and from other ActionHashes.
+pub type ActionEntity<T> = Entity<T, ActionHash>;
+
+impl<T,I> Entity<T,I>
+where
+    I: Clone + Into<AnyLinkableHash>,
+{
 
     /// Link this entity to the given base with a specific tag.  Shortcut for [`hdk::prelude::create_link`]
-    pub fn link_from<L,E>(&self, base: &EntryHash, link_type: L, tag_input: Option<Vec<u8>>) -> UtilsResult<ActionHash>
+    pub fn link_from<L,E>(&self, base: &I, link_type: L, tag_input: Option<Vec<u8>>) -> UtilsResult<ActionHash>
     where
 	ScopedLinkType: TryFrom<L, Error = E>,
         WasmError: From<E>,
     {
-        Ok( match tag_input {
-	    None => create_link( base.to_owned(), self.id.to_owned(), link_type, () )?,
-	    Some(input) => create_link( base.to_owned(), self.id.to_owned(), link_type, input )?,
-	})
+	create_tagged_link( base.to_owned().into(), self.id.to_owned().into(), link_type, tag_input )
     }
 
     /// Link the given target to this entity with a specific tag.  Shortcut for [`hdk::prelude::create_link`]
-    pub fn link_to<L,E>(&self, target: &EntryHash, link_type: L, tag_input: Option<Vec<u8>>) -> UtilsResult<ActionHash>
+    pub fn link_to<L,E>(&self, target: &I, link_type: L, tag_input: Option<Vec<u8>>) -> UtilsResult<ActionHash>
+    where
+	ScopedLinkType: TryFrom<L, Error = E>,
+        WasmError: From<E>,
+    {
+	create_tagged_link( self.id.to_owned().into(), target.to_owned().into(), link_type, tag_input )
+    }
+
+    /// Same as [`Entity::link_from`] with a tag encoded by [`encode_tag`](crate::encode_tag)
+    pub fn link_from_tagged<L,E,Tag>(&self, base: &I, link_type: L, tag: &Tag) -> UtilsResult<ActionHash>
     where
 	ScopedLinkType: TryFrom<L, Error = E>,
         WasmError: From<E>,
+	Tag: Serialize + ?Sized,
     {
-        Ok( match tag_input {
-	    None => create_link( self.id.to_owned(), target.to_owned(), link_type, () )?,
-	    Some(input) => create_link( self.id.to_owned(), target.to_owned(), link_type, input )?,
-	})
+	self.link_from( base, link_type, Some( encode_tag( tag )?.into_inner() ) )
+    }
+
+    /// Same as [`Entity::link_to`] with a tag encoded by [`encode_tag`](crate::encode_tag)
+    pub fn link_to_tagged<L,E,Tag>(&self, target: &I, link_type: L, tag: &Tag) -> UtilsResult<ActionHash>
+    where
+	ScopedLinkType: TryFrom<L, Error = E>,
+        WasmError: From<E>,
+	Tag: Serialize + ?Sized,
+    {
+	self.link_to( target, link_type, Some( encode_tag( tag )?.into_inner() ) )
     }
 
     /// Delete an existing link from the 'current_base' and create a new link from the 'new_base'
-    pub fn move_link_from<LT,E>(&self, link_type: LT, tag_input: Option<Vec<u8>>, current_base: &EntryHash, new_base: &EntryHash) -> UtilsResult<ActionHash>
+    pub fn move_link_from<LT,E>(&self, link_type: LT, tag_input: Option<Vec<u8>>, current_base: &I, new_base: &I) -> UtilsResult<ActionHash>
     where
 	LT: LinkTypeFilterExt + Clone + std::fmt::Debug,
         ScopedLinkType: TryFrom<LT, Error = E>,
         WasmError: From<E>,
     {
-	let tag_filter = tag_input.to_owned().map( |tag| LinkTag::new( tag ) );
+	let tag_filter = tag_input.to_owned().map( LinkTag::new );
 	let all_links = get_links(
 	    current_base.clone(),
 	    link_type.to_owned(),
 	    tag_filter.to_owned(),
 	)?;
 
+	let id : AnyLinkableHash = self.id.to_owned().into();
+
 	if let Some(current_link) = all_links.into_iter().find(|link| {
-	    link.target == self.id.to_owned().into()
+	    link.target == id
 	}) {
             delete_link( current_link.create_link_hash )?;
 	}
 	else {
-	    Err(UtilsError::UnexpectedState(format!("Aborting 'move_from_link' because existing link was not found")))?;
+	    Err(UtilsError::UnexpectedState("Aborting 'move_from_link' because existing link was not found".to_string()))?;
 	};
 
 	let new_links = get_links(
@@ -111,7 +195,7 @@ impl<T> Entity<T> {
 	)?;
 
 	if let Some(existing_link) = new_links.into_iter().find(|link| {
-	    link.target == self.id.to_owned().into()
+	    link.target == id
 	}) {
             Ok( existing_link.create_link_hash )
 	}
@@ -119,6 +203,263 @@ impl<T> Entity<T> {
             self.link_from( new_base, link_type, tag_input )
 	}
     }
+
+    /// Same as [`Entity::move_link_from`] with a tag encoded by [`encode_tag`](crate::encode_tag)
+    pub fn move_link_from_tagged<LT,E,Tag>(&self, link_type: LT, tag: &Tag, current_base: &I, new_base: &I) -> UtilsResult<ActionHash>
+    where
+	LT: LinkTypeFilterExt + Clone + std::fmt::Debug,
+        ScopedLinkType: TryFrom<LT, Error = E>,
+        WasmError: From<E>,
+	Tag: Serialize + ?Sized,
+    {
+	self.move_link_from( link_type, Some( encode_tag( tag )?.into_inner() ), current_base, new_base )
+    }
+
+    /// Delete an existing link to the 'current_target' and create a new link to the 'new_target'
+    pub fn move_link_to<LT,E>(&self, link_type: LT, tag_input: Option<Vec<u8>>, current_target: &I, new_target: &I) -> UtilsResult<ActionHash>
+    where
+	LT: LinkTypeFilterExt + Clone + std::fmt::Debug,
+        ScopedLinkType: TryFrom<LT, Error = E>,
+        WasmError: From<E>,
+    {
+	let tag_filter = tag_input.to_owned().map( LinkTag::new );
+	let all_links = get_links(
+	    self.id.to_owned(),
+	    link_type.to_owned(),
+	    tag_filter.to_owned(),
+	)?;
+
+	let current_target : AnyLinkableHash = current_target.to_owned().into();
+	let new_target_hash : AnyLinkableHash = new_target.to_owned().into();
+
+	if let Some(current_link) = all_links.into_iter().find(|link| {
+	    link.target == current_target
+	}) {
+            delete_link( current_link.create_link_hash )?;
+	}
+	else {
+	    Err(UtilsError::UnexpectedState("Aborting 'move_link_to' because existing link was not found".to_string()))?;
+	};
+
+	let remaining_links = get_links(
+	    self.id.to_owned(),
+	    link_type.to_owned(),
+	    tag_filter,
+	)?;
+
+	if let Some(existing_link) = remaining_links.into_iter().find(|link| {
+	    link.target == new_target_hash
+	}) {
+            Ok( existing_link.create_link_hash )
+	}
+	else {
+            self.link_to( new_target, link_type, tag_input )
+	}
+    }
+
+    /// Same as [`Entity::move_link_to`] with a tag encoded by [`encode_tag`](crate::encode_tag)
+    pub fn move_link_to_tagged<LT,E,Tag>(&self, link_type: LT, tag: &Tag, current_target: &I, new_target: &I) -> UtilsResult<ActionHash>
+    where
+	LT: LinkTypeFilterExt + Clone + std::fmt::Debug,
+        ScopedLinkType: TryFrom<LT, Error = E>,
+        WasmError: From<E>,
+	Tag: Serialize + ?Sized,
+    {
+	self.move_link_to( link_type, Some( encode_tag( tag )?.into_inner() ), current_target, new_target )
+    }
+
+    /// Link this entity from the [`Path`] made of the given segments
+    ///
+    /// The path is ensured first using the same link type for the path's own links, so read them
+    /// back with [`get_entities_for_path`](crate::get_entities_for_path) or
+    /// [`get_entity_collection_for_path`](crate::get_entity_collection_for_path) which skip the
+    /// links to child paths.
+    pub fn link_from_path<S,L,E>(&self, segments: S, link_type: L, tag_input: Option<Vec<u8>>) -> UtilsResult<ActionHash>
+    where
+	S: IntoIterator,
+	S::Item: std::fmt::Display,
+	L: Clone,
+	ScopedLinkType: TryFrom<L, Error = E>,
+        WasmError: From<E>,
+    {
+	let path = path_from_collection( segments )?;
+	path.clone().typed( link_type.to_owned() )?.ensure()?;
+
+	create_tagged_link( path.path_entry_hash()?.into(), self.id.to_owned().into(), link_type, tag_input )
+    }
+
+    /// Link this entity to 'other' and 'other' back to this entity
+    ///
+    /// Returns the forward and reverse create link hashes.  Both links are written in the same
+    /// zome call so if either fails neither is committed.
+    pub fn link_bidirectional<LF,LR,EF,ER>(&self, other: &I, forward_type: LF, reverse_type: LR, tag_input: Option<Vec<u8>>) -> UtilsResult<(ActionHash, ActionHash)>
+    where
+	ScopedLinkType: TryFrom<LF, Error = EF> + TryFrom<LR, Error = ER>,
+        WasmError: From<EF> + From<ER>,
+    {
+	let forward = self.link_to( other, forward_type, tag_input.to_owned() )?;
+	let reverse = self.link_from( other, reverse_type, tag_input )?;
+
+	Ok( (forward, reverse) )
+    }
+
+    /// Delete the links created by [`Entity::link_bidirectional`]
+    ///
+    /// Returns the delete hashes for every matching link in both directions.
+    pub fn unlink_bidirectional<LF,LR>(&self, other: &I, forward_type: LF, reverse_type: LR, tag_input: Option<Vec<u8>>) -> UtilsResult<Vec<ActionHash>>
+    where
+	LF: LinkTypeFilterExt,
+	LR: LinkTypeFilterExt,
+    {
+	let mut deleted = self.unlink_to( other, forward_type, tag_input.to_owned() )?;
+	deleted.extend( self.unlink_from( other, reverse_type, tag_input )? );
+
+	Ok( deleted )
+    }
+
+    /// Delete every link from the given base to this entity
+    ///
+    /// Returns the delete hashes (empty if there were no matching links).
+    pub fn unlink_from<LT>(&self, base: &I, link_type: LT, tag_input: Option<Vec<u8>>) -> UtilsResult<Vec<ActionHash>>
+    where
+	LT: LinkTypeFilterExt,
+    {
+	self.unlink_from_with( base, link_type, tag_input, &UnlinkOptions::default() )
+    }
+
+    /// Same as [`Entity::unlink_from`] with options for limiting which links are deleted
+    pub fn unlink_from_with<LT>(&self, base: &I, link_type: LT, tag_input: Option<Vec<u8>>, options: &UnlinkOptions) -> UtilsResult<Vec<ActionHash>>
+    where
+	LT: LinkTypeFilterExt,
+    {
+	delete_links_between( base.to_owned().into(), self.id.to_owned().into(), link_type, tag_input, options )
+    }
+
+    /// Delete every link from this entity to the given target
+    ///
+    /// Returns the delete hashes (empty if there were no matching links).
+    pub fn unlink_to<LT>(&self, target: &I, link_type: LT, tag_input: Option<Vec<u8>>) -> UtilsResult<Vec<ActionHash>>
+    where
+	LT: LinkTypeFilterExt,
+    {
+	self.unlink_to_with( target, link_type, tag_input, &UnlinkOptions::default() )
+    }
+
+    /// Same as [`Entity::unlink_to`] with options for limiting which links are deleted
+    pub fn unlink_to_with<LT>(&self, target: &I, link_type: LT, tag_input: Option<Vec<u8>>, options: &UnlinkOptions) -> UtilsResult<Vec<ActionHash>>
+    where
+	LT: LinkTypeFilterExt,
+    {
+	delete_links_between( self.id.to_owned().into(), target.to_owned().into(), link_type, tag_input, options )
+    }
+}
+
+
+fn create_tagged_link<L,E>(base: AnyLinkableHash, target: AnyLinkableHash, link_type: L, tag_input: Option<Vec<u8>>) -> UtilsResult<ActionHash>
+where
+    ScopedLinkType: TryFrom<L, Error = E>,
+    WasmError: From<E>,
+{
+    Ok( match tag_input {
+	None => create_link( base, target, link_type, () )?,
+	Some(input) => create_link( base, target, link_type, input )?,
+    })
+}
+
+fn delete_links_between<LT>(base: AnyLinkableHash, target: AnyLinkableHash, link_type: LT, tag_input: Option<Vec<u8>>, options: &UnlinkOptions) -> UtilsResult<Vec<ActionHash>>
+where
+    LT: LinkTypeFilterExt,
+{
+    let links = get_links(
+	base,
+	link_type,
+	tag_input.map( LinkTag::new ),
+    )?;
+
+    let mut deleted = Vec::new();
+
+    // Sorted so that 'first_only' deletes the earliest link
+    for link in links_after( links, None ).into_iter().filter( |link| link.target == target ) {
+	deleted.push( delete_link( link.create_link_hash )? );
+
+	if options.first_only {
+	    break;
+	}
+    }
+
+    Ok( deleted )
+}
+
+
+/// An entity along with its deletion status
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityStatus<T> {
+    /// The entity's latest state
+    pub entity: Entity<T>,
+
+    /// The earliest Delete action for the entity's Create action (if it has been deleted)
+    pub deleted: Option<ActionHash>,
+}
+
+impl<T> EntityStatus<T> {
+    /// Returns `true` if the entity has been deleted
+    pub fn is_deleted(&self) -> bool {
+	self.deleted.is_some()
+    }
+}
+
+
+/// The result of [`update_entity_if`](crate::update_entity_if)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ConditionalUpdate<T> {
+    /// The current entry was the expected one and the entity was updated
+    Updated(Entity<T>),
+
+    /// The current entry was not the expected one so nothing was written
+    Conflict {
+	/// The entry address that was expected
+	expected: EntryHash,
+
+	/// The entity's current version
+	current: Entity<T>,
+    },
+}
+
+impl<T> ConditionalUpdate<T> {
+    /// The updated entity, or [`UtilsError::ContentConflict`] if there was a conflict
+    pub fn into_result(self) -> UtilsResult<Entity<T>> {
+	match self {
+	    ConditionalUpdate::Updated(entity) => Ok( entity ),
+	    ConditionalUpdate::Conflict { expected, current } => Err(UtilsError::ContentConflict {
+		expected,
+		found: current.address,
+	    }),
+	}
+    }
+}
+
+
+/// A link that was deleted along with the action that deleted it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemovedLink {
+    /// The link's base (not included in [`Link`])
+    pub base: EntryHash,
+
+    /// The link as it was before it was deleted
+    pub link: Link,
+
+    /// The DeleteLink action
+    pub delete: ActionHash,
+}
+
+/// The result of deleting an entity along with its links
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteSummary {
+    /// The Delete action for the entity's Create action
+    pub action: ActionHash,
+
+    /// Every link that was deleted along with the entity
+    pub links: Vec<RemovedLink>,
 }
 
 
@@ -148,6 +489,8 @@ pub mod tests {
 	    address: ehash,
 	    ctype: EntityType::new( "boolean", "primitive" ),
 	    content: true,
+	    meta: None,
+	    version: None,
 	};
 
 	assert_eq!( item.ctype.name, "boolean" );
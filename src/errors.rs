@@ -1,6 +1,7 @@
 use std::convert::Infallible;
 use thiserror::Error;
 use hdk::prelude::*;
+use serde_json::{ json, Value };
 
 /// The potential Error types for this CRUD library
 #[derive(Error, Debug)]
@@ -36,15 +37,231 @@ pub enum UtilsError {
     #[error("The given Action address ({0}) is not a Create action type")]
     NotOriginEntryError(ActionHash),
 
+    /// The given Action address is not the latest version so updating it would create a branch
+    #[error("The given Action address ({given}) is not the latest version; the latest is {latest}")]
+    StaleUpdate {
+	given: ActionHash,
+	latest: ActionHash,
+    },
+
+    /// The entity's Create action has been deleted
+    #[error("Entity '{0}' has been deleted by action {1}")]
+    EntityDeleted(EntryHash, ActionHash),
+
+    /// The Create action of an entity identified by its ActionHash has been deleted
+    #[error("Entity '{0}' has been deleted by action {1}")]
+    ActionEntityDeleted(ActionHash, ActionHash),
+
+    /// The entity's current entry is not the expected one (see
+    /// [`ConditionalUpdate::into_result`](crate::ConditionalUpdate::into_result))
+    #[error("Expected the current entry to be {expected} but found {found}")]
+    ContentConflict {
+	expected: EntryHash,
+	found: EntryHash,
+    },
+
+    /// The calling agent is not the author of the entity's Create action
+    #[error("Agent {caller} is not the author ({author}) of this entity")]
+    NotAuthor {
+	author: AgentPubKey,
+	caller: AgentPubKey,
+    },
+
+    /// Following an update chain exceeded the maximum depth
+    #[error("History for Action address '{0}' exceeded the maximum depth of {1}")]
+    HistoryTooDeep(ActionHash, usize),
+
+    /// Following an update chain returned to an Action that was already visited
+    #[error("History contains a cycle at Action address '{0}'")]
+    HistoryCycle(ActionHash),
+
+    /// An [`EntityId`](crate::EntityId) string belongs to a different entry model
+    #[error("Entity ID is for type '{found}'; expected '{expected}'")]
+    EntityIdTypeMismatch {
+	expected: String,
+	found: String,
+    },
+
+    /// An [`EntityId`](crate::EntityId) string could not be parsed
+    #[error("Invalid entity ID '{0}': {1}")]
+    InvalidEntityId(String, String),
+
+    /// A link tag could not be encoded or decoded
+    #[error("Link tag encoding error: {0}")]
+    LinkTagError(String),
+
+    /// A page was requested with a limit of zero
+    #[error("Page limit must be greater than zero")]
+    InvalidPageLimit,
+
     // /// Indicates that the CRUD model was broken because there are multiple links with the tag
     // /// 'origin'
     // #[error("Found multiple origin links for entry: {0:?}")]
     // MultipleOriginsError(EntryHash),
 }
 
+impl UtilsError {
+    /// A stable identifier for this error's variant
+    pub fn kind(&self) -> &'static str {
+	match self {
+	    UtilsError::HDKError(_) => "HdkError",
+	    UtilsError::UnexpectedState(_) => "UnexpectedState",
+	    UtilsError::DeserializationError(..) => "DeserializationError",
+	    UtilsError::WrongEntryTypeError(..) => "WrongEntryType",
+	    UtilsError::RecordHasNoEntry(..) => "RecordHasNoEntry",
+	    UtilsError::EntryNotFoundError(..) => "EntryNotFound",
+	    UtilsError::ActionNotFoundError(..) => "ActionNotFound",
+	    UtilsError::NotOriginEntryError(_) => "NotOriginEntry",
+	    UtilsError::StaleUpdate { .. } => "StaleUpdate",
+	    UtilsError::ContentConflict { .. } => "ContentConflict",
+	    UtilsError::EntityDeleted(..) => "EntityDeleted",
+	    UtilsError::ActionEntityDeleted(..) => "ActionEntityDeleted",
+	    UtilsError::NotAuthor { .. } => "NotAuthor",
+	    UtilsError::HistoryTooDeep(..) => "HistoryTooDeep",
+	    UtilsError::HistoryCycle(_) => "HistoryCycle",
+	    UtilsError::EntityIdTypeMismatch { .. } => "EntityIdTypeMismatch",
+	    UtilsError::InvalidEntityId(..) => "InvalidEntityId",
+	    UtilsError::LinkTagError(_) => "LinkTagError",
+	    UtilsError::InvalidPageLimit => "InvalidPageLimit",
+	}
+    }
+
+    /// This error's kind, message and fields
+    pub fn payload(&self) -> ErrorPayload {
+	let data = match self {
+	    UtilsError::HDKError(error) => json!({
+		"error": format!("{:?}", error.error ),
+	    }),
+	    UtilsError::UnexpectedState(reason) => json!({
+		"reason": reason,
+	    }),
+	    UtilsError::DeserializationError(expected, found) => json!({
+		"expected": expected,
+		"found": match found {
+		    Some(EntryType::App(def)) => entry_def( &def.zome_index, &def.entry_index ),
+		    Some(entry_type) => json!( format!("{:?}", entry_type ) ),
+		    None => Value::Null,
+		},
+	    }),
+	    UtilsError::WrongEntryTypeError(zome_index, entry_index, found_zome_index, found_entry_index) => json!({
+		"expected": entry_def( zome_index, entry_index ),
+		"found": entry_def( found_zome_index, found_entry_index ),
+	    }),
+	    UtilsError::RecordHasNoEntry(action, action_type) => json!({
+		"action": action.to_string(),
+		"action_type": action_type.to_string(),
+	    }),
+	    UtilsError::EntryNotFoundError(entry, reason) => json!({
+		"entry": entry.to_string(),
+		"reason": reason,
+	    }),
+	    UtilsError::ActionNotFoundError(action, reason) => json!({
+		"action": action.to_string(),
+		"reason": reason,
+	    }),
+	    UtilsError::NotOriginEntryError(action) => json!({
+		"action": action.to_string(),
+	    }),
+	    UtilsError::StaleUpdate { given, latest } => json!({
+		"given": given.to_string(),
+		"latest": latest.to_string(),
+	    }),
+	    UtilsError::ContentConflict { expected, found } => json!({
+		"expected": expected.to_string(),
+		"found": found.to_string(),
+	    }),
+	    UtilsError::EntityDeleted(id, delete) => json!({
+		"id": id.to_string(),
+		"delete": delete.to_string(),
+	    }),
+	    UtilsError::ActionEntityDeleted(id, delete) => json!({
+		"id": id.to_string(),
+		"delete": delete.to_string(),
+	    }),
+	    UtilsError::NotAuthor { author, caller } => json!({
+		"author": author.to_string(),
+		"caller": caller.to_string(),
+	    }),
+	    UtilsError::HistoryTooDeep(action, max_depth) => json!({
+		"action": action.to_string(),
+		"max_depth": max_depth,
+	    }),
+	    UtilsError::HistoryCycle(action) => json!({
+		"action": action.to_string(),
+	    }),
+	    UtilsError::EntityIdTypeMismatch { expected, found } => json!({
+		"expected": expected,
+		"found": found,
+	    }),
+	    UtilsError::InvalidEntityId(input, reason) => json!({
+		"input": input,
+		"reason": reason,
+	    }),
+	    UtilsError::LinkTagError(reason) => json!({
+		"reason": reason,
+	    }),
+	    UtilsError::InvalidPageLimit => json!({}),
+	};
+
+	ErrorPayload {
+	    kind: self.kind().to_string(),
+	    message: self.to_string(),
+	    data,
+	}
+    }
+}
+
+fn entry_def(zome_index: &ZomeIndex, entry_index: &EntryDefIndex) -> Value {
+    json!({
+	"zome_index": zome_index.0,
+	"entry_index": entry_index.0,
+    })
+}
+
+impl Serialize for UtilsError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+	self.payload().serialize( serializer )
+    }
+}
+
+
+/// The serialized form of a [`UtilsError`]
+///
+/// Converting a [`UtilsError`] into a `WasmError` puts the JSON of this payload in the `Guest`
+/// error string, so clients can match on `kind` instead of the message.
+///
+/// ```json
+/// { "kind": "EntryNotFound", "message": "Record not found for Entry address 'uhCEk…': ", "data": { "entry": "uhCEk…", "reason": "" } }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorPayload {
+    /// A stable identifier for the error variant (see [`UtilsError::kind`])
+    pub kind: String,
+
+    /// The human readable error message
+    pub message: String,
+
+    /// The error's fields; hashes are base64 strings (eg. `uhCEk…`)
+    pub data: Value,
+}
+
+impl ErrorPayload {
+    /// Decode the payload from a `WasmError` created from a [`UtilsError`]
+    pub fn from_wasm_error(error: &WasmError) -> Option<Self> {
+	match &error.error {
+	    WasmErrorInner::Guest(text) => serde_json::from_str( text ).ok(),
+	    _ => None,
+	}
+    }
+}
+
 impl From<UtilsError> for WasmError  {
     fn from(error: UtilsError) -> Self {
-	wasm_error!(WasmErrorInner::Guest(format!("{}", error)))
+	let payload = error.payload();
+	let text = serde_json::to_string( &payload )
+	    .unwrap_or( payload.message );
+
+	wasm_error!(WasmErrorInner::Guest(text))
     }
 }
 
@@ -62,3 +279,37 @@ impl From<Infallible> for UtilsError {
 
 /// The Result type for `Result<T, UtilsError>` ([UtilsError])
 pub type UtilsResult<T> = Result<T, UtilsError>;
+
+
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn error_payload_test() {
+	let entry = EntryHash::from_raw_32( vec![1; 32] );
+	let error = UtilsError::EntryNotFoundError(entry.to_owned(), None);
+	let message = error.to_string();
+
+	let wasm_error : WasmError = error.into();
+	let payload = ErrorPayload::from_wasm_error( &wasm_error ).unwrap();
+	assert_eq!( payload.kind, "EntryNotFound" );
+	assert_eq!( payload.message, message );
+	assert_eq!( payload.data["entry"], entry.to_string() );
+	assert!( payload.data["reason"].is_null() );
+
+	let error = UtilsError::WrongEntryTypeError(0.into(), 0.into(), 0.into(), 1.into());
+	let payload = ErrorPayload::from_wasm_error( &error.into() ).unwrap();
+	assert_eq!( payload.kind, "WrongEntryType" );
+	assert_eq!( payload.data["expected"], json!({ "zome_index": 0, "entry_index": 0 }) );
+	assert_eq!( payload.data["found"], json!({ "zome_index": 0, "entry_index": 1 }) );
+
+	let error = UtilsError::HistoryTooDeep(ActionHash::from_raw_32( vec![2; 32] ), 10);
+	let encoded = holochain_serialized_bytes::encode( &error ).unwrap();
+	let decoded : ErrorPayload = holochain_serialized_bytes::decode( &encoded ).unwrap();
+	assert_eq!( decoded, error.payload() );
+
+	assert!( ErrorPayload::from_wasm_error( &wasm_error!(WasmErrorInner::Guest("plain text".to_string())) ).is_none() );
+    }
+}
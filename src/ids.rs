@@ -0,0 +1,148 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::{ Hash, Hasher };
+use std::marker::PhantomData;
+use std::str::FromStr;
+use hdk::prelude::*;
+use crate::entities::{ Entity, EntryModel };
+use crate::errors::{ UtilsResult, UtilsError };
+
+
+/// An entity ID that knows which entry model it belongs to
+///
+/// On the wire it is the plain EntryHash.  As a string it is prefixed with the lowercase
+/// [`EntryModel::name`], eg. `post:uhCEk…`, and parsing a string with a different prefix fails
+/// with [`UtilsError::EntityIdTypeMismatch`].
+pub struct EntityId<T, ET> {
+    hash: EntryHash,
+    model: PhantomData<fn() -> (T, ET)>,
+}
+
+impl<T, ET> EntityId<T, ET> {
+    pub fn new(hash: EntryHash) -> Self {
+	EntityId {
+	    hash,
+	    model: PhantomData,
+	}
+    }
+
+    /// The underlying EntryHash
+    pub fn hash(&self) -> &EntryHash {
+	&self.hash
+    }
+
+    pub fn into_hash(self) -> EntryHash {
+	self.hash
+    }
+}
+
+impl<T, ET> EntityId<T, ET>
+where
+    T: EntryModel<ET>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    /// The string prefix for this entry model
+    pub fn prefix() -> String {
+	T::name().to_lowercase()
+    }
+}
+
+impl<T> Entity<T> {
+    /// This entity's ID typed by its entry model
+    pub fn entity_id<ET>(&self) -> EntityId<T, ET> {
+	EntityId::new( self.id.to_owned() )
+    }
+}
+
+
+impl<T, ET> Clone for EntityId<T, ET> {
+    fn clone(&self) -> Self {
+	EntityId::new( self.hash.to_owned() )
+    }
+}
+
+impl<T, ET> PartialEq for EntityId<T, ET> {
+    fn eq(&self, other: &Self) -> bool {
+	self.hash == other.hash
+    }
+}
+
+impl<T, ET> Eq for EntityId<T, ET> {}
+
+impl<T, ET> Hash for EntityId<T, ET> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+	self.hash.hash( state )
+    }
+}
+
+impl<T, ET> fmt::Debug for EntityId<T, ET> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	f.debug_tuple("EntityId").field( &self.hash ).finish()
+    }
+}
+
+impl<T, ET> From<EntryHash> for EntityId<T, ET> {
+    fn from(hash: EntryHash) -> Self {
+	EntityId::new( hash )
+    }
+}
+
+impl<T, ET> From<EntityId<T, ET>> for EntryHash {
+    fn from(id: EntityId<T, ET>) -> Self {
+	id.hash
+    }
+}
+
+impl<T, ET> From<EntityId<T, ET>> for AnyLinkableHash {
+    fn from(id: EntityId<T, ET>) -> Self {
+	id.hash.into()
+    }
+}
+
+impl<T, ET> Serialize for EntityId<T, ET> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+	self.hash.serialize( serializer )
+    }
+}
+
+impl<'de, T, ET> Deserialize<'de> for EntityId<T, ET> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+	Ok( EntityId::new( EntryHash::deserialize( deserializer )? ) )
+    }
+}
+
+
+impl<T, ET> fmt::Display for EntityId<T, ET>
+where
+    T: EntryModel<ET>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	write!( f, "{}:{}", Self::prefix(), self.hash )
+    }
+}
+
+impl<T, ET> FromStr for EntityId<T, ET>
+where
+    T: EntryModel<ET>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    type Err = UtilsError;
+
+    fn from_str(input: &str) -> UtilsResult<Self> {
+	let (prefix, hash) = input.split_once(':')
+	    .ok_or_else( || UtilsError::InvalidEntityId(input.to_string(), "missing '<type>:' prefix".to_string()) )?;
+
+	if prefix != Self::prefix() {
+	    return Err(UtilsError::EntityIdTypeMismatch {
+		expected: Self::prefix(),
+		found: prefix.to_string(),
+	    });
+	}
+
+	let hash = EntryHash::try_from( hash )
+	    .map_err( |error| UtilsError::InvalidEntityId(input.to_string(), format!("{:?}", error )) )?;
+
+	Ok( EntityId::new( hash ) )
+    }
+}
@@ -6,16 +6,49 @@
 
 mod errors;
 mod entities;
+mod collections;
+mod ids;
+mod options;
+mod branches;
+mod tags;
+mod time_index;
+mod validation;
+mod versions;
 mod utils;
 
+pub mod by_action;
+
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
+
 use std::convert::TryFrom;
+use std::collections::HashSet;
 use hdk::prelude::*;
 
-pub use entities::{ Entity, EmptyEntity, EntityType, EntryModel };
-pub use errors::{ UtilsResult, UtilsError };
+pub use entities::{ Entity, ActionEntity, EntityMeta, EmptyEntity, EntityStatus, ConditionalUpdate, EntityType, EntryModel, DeleteSummary, RemovedLink };
+#[cfg(feature = "derive")]
+pub use hc_crud_ceps_derive::EntryModel;
+pub use errors::{ UtilsResult, UtilsError, ErrorPayload };
+pub use ids::EntityId;
+pub use collections::{ Page, LinkCursor, EntityCollection, links_after };
+pub use options::{
+    UpdateOptions, UnlinkOptions, DeleteOptions, ReadOptions, ReadStrategy, ValidationOptions,
+    DEFAULT_MAX_DEPTH,
+};
+pub use tags::{ encode_tag, decode_tag };
+pub use time_index::{ TimeIndex, TimeBucket, get_entities_in_range, get_entities_in_range_with };
+pub use versions::Upgrade;
+pub use validation::{
+    validate_ceps_op, validate_ceps_op_with, validate_update, validate_update_chain,
+    validate_delete, validate_delete_target,
+};
+pub use branches::{
+    BranchResolver, EarliestUpdate, LatestUpdate, EarliestUpdateByHash, OriginalAuthorUpdate,
+};
+use utils::RecordSource;
 pub use utils::{
     now, find_latest_link, path_from_collection,
-    trace_action_history, to_entry_type,
+    trace_action_history, trace_action_history_with, to_entry_type, to_entry_type_versioned,
 };
 
 
@@ -30,15 +63,80 @@ pub fn get_origin_address(addr: &ActionHash) -> UtilsResult<EntryHash> {
 
 /// Get the record for any given EntryHash
 pub fn fetch_record(addr: &EntryHash) -> UtilsResult<(ActionHash, Record)> {
-    let record = get( addr.to_owned(), GetOptions::latest() )?
+    fetch_record_with( addr, &ReadOptions::default() )
+}
+
+/// Get the record for any given EntryHash using the given [`ReadOptions::strategy`]
+pub fn fetch_record_with(addr: &EntryHash, options: &ReadOptions) -> UtilsResult<(ActionHash, Record)> {
+    let record = RecordSource::new( options.strategy )?.get( addr.to_owned() )?
 	.ok_or( UtilsError::EntryNotFoundError(addr.to_owned(), Some("".to_string())) )?;
 
     Ok( (record.action_address().to_owned(), record) )
 }
 
+/// Get the Create record for any given entity ID, even if it has been deleted
+pub fn fetch_origin_record(id: &EntryHash) -> UtilsResult<(ActionHash, Record)> {
+    fetch_origin_record_with( id, &ReadOptions::default() )
+}
+
+/// Get the Create record for any given entity ID using the given [`ReadOptions::strategy`]
+pub fn fetch_origin_record_with(id: &EntryHash, options: &ReadOptions) -> UtilsResult<(ActionHash, Record)> {
+    fetch_origin_record_from( id, &RecordSource::new( options.strategy )? )
+}
+
+fn fetch_origin_record_from(id: &EntryHash, source: &RecordSource) -> UtilsResult<(ActionHash, Record)> {
+    let details = source.get_details( id.to_owned() )?
+	.ok_or( UtilsError::EntryNotFoundError(id.to_owned(), Some("".to_string())) )?;
+
+    let (entry, actions) = match details {
+	Details::Entry(details) => (details.entry, details.actions),
+	Details::Record(details) => (
+	    details.record.entry().to_owned().into_option()
+		.ok_or( UtilsError::EntryNotFoundError(id.to_owned(), None) )?,
+	    vec![ details.record.signed_action().to_owned() ],
+	),
+    };
+
+    let creates = actions.iter()
+	.filter( |sh| matches!( sh.action(), Action::Create(_) ) )
+	.cloned()
+	.collect();
+
+    match find_earliest_action( creates ) {
+	Some(create) => Ok( (create.action_address().to_owned(), Record::new( create, Some(entry) )) ),
+	None => match actions.first() {
+	    Some(action) => Err(UtilsError::NotOriginEntryError(action.action_address().to_owned())),
+	    None => Err(UtilsError::EntryNotFoundError(id.to_owned(), None)),
+	},
+    }
+}
+
+/// Get the earliest Delete action (if any) for the given Action
+pub fn find_delete(addr: &ActionHash) -> UtilsResult<Option<ActionHash>> {
+    find_delete_with( addr, &ReadOptions::default() )
+}
+
+/// Get the earliest Delete action (if any) for the given Action using the given
+/// [`ReadOptions::strategy`]
+pub fn find_delete_with(addr: &ActionHash, options: &ReadOptions) -> UtilsResult<Option<ActionHash>> {
+    find_delete_from( addr, &RecordSource::new( options.strategy )? )
+}
+
+pub(crate) fn find_delete_from(addr: &ActionHash, source: &RecordSource) -> UtilsResult<Option<ActionHash>> {
+    let details = source.get_details( addr.to_owned() )?
+	.ok_or( UtilsError::ActionNotFoundError(addr.to_owned(), Some("".to_string())) )?;
+    let deletes = match details {
+	Details::Record(details) => details.deletes,
+	Details::Entry(details) => details.deletes,
+    };
+
+    Ok( find_earliest_action( deletes )
+	.map( |delete| delete.action_address().to_owned() ) )
+}
+
 /// Finds and returns the Action with the earliest timestamp from a list
 pub fn find_earliest_action(updates: Vec<SignedHashed<Action>>) -> Option<SignedHashed<Action>> {
-    if updates.len() == 0 {
+    if updates.is_empty() {
 	None
     }
     else {
@@ -61,37 +159,80 @@ pub fn find_earliest_action(updates: Vec<SignedHashed<Action>>) -> Option<Signed
 
 /// Follow the trail of (earliest) updates and return the full Action path.
 pub fn follow_updates(hash: &ActionHash, trace: Option<Vec<ActionHash>>) -> UtilsResult<Vec<ActionHash>> {
-    let mut history = trace.unwrap_or( Vec::new() );
-    history.push( hash.to_owned() );
-
-    let details = get_details( hash.to_owned(), GetOptions::latest() )?
-	.ok_or( UtilsError::ActionNotFoundError(hash.to_owned(), Some("".to_string())) )?;
-    let updates = match details {
-	Details::Record(details) => details.updates,
-	Details::Entry(details) => details.updates,
-    };
+    let mut history = trace.unwrap_or_default();
 
-    Ok( match find_earliest_action( updates ) {
-	None => history,
-	Some(next_update) => follow_updates( next_update.action_address(), Some(history) )?,
-    })
+    history.extend( follow_updates_with( hash, &ReadOptions::default() )? );
+
+    Ok( history )
+}
+
+/// Follow the trail of updates chosen by the given [`BranchResolver`] and return the full Action
+/// path.
+///
+/// Fails with [`UtilsError::HistoryTooDeep`] after visiting [`ReadOptions::max_depth`] actions or
+/// with [`UtilsError::HistoryCycle`] if an action is revisited.
+pub fn follow_updates_with(hash: &ActionHash, options: &ReadOptions) -> UtilsResult<Vec<ActionHash>> {
+    follow_updates_from( hash, options, &RecordSource::new( options.strategy )? )
+}
+
+pub(crate) fn follow_updates_from(hash: &ActionHash, options: &ReadOptions, source: &RecordSource) -> UtilsResult<Vec<ActionHash>> {
+    let mut history = Vec::new();
+    let mut visited = HashSet::new();
+    let mut origin : Option<Action> = None;
+    let mut next = Some( hash.to_owned() );
+
+    while let Some(current) = next {
+	if history.len() >= options.max_depth {
+	    Err(UtilsError::HistoryTooDeep(hash.to_owned(), options.max_depth))?;
+	}
+	if !visited.insert( current.to_owned() ) {
+	    Err(UtilsError::HistoryCycle(current.to_owned()))?;
+	}
+
+	let details = source.get_details( current.to_owned() )?
+	    .ok_or( UtilsError::ActionNotFoundError(current.to_owned(), Some("".to_string())) )?;
+	let (action, updates) = match details {
+	    Details::Record(details) => (details.record.action().to_owned(), details.updates),
+	    Details::Entry(_) => Err(UtilsError::UnexpectedState(format!("Expected Record details for Action address '{}'", current )))?,
+	};
+	let origin = origin.get_or_insert( action );
+
+	history.push( current );
+
+	next = match updates.is_empty() {
+	    true => None,
+	    false => options.resolver.resolve( origin, updates )
+		.map( |update| update.action_address().to_owned() ),
+	};
+    }
+
+    Ok( history )
 }
 
 /// Get the latest Record for any given entity ID
+///
+/// Delete actions are not considered; see [`find_delete`].
 pub fn fetch_record_latest(id: &EntryHash) -> UtilsResult<(ActionHash, Record)> {
-    let (action_hash, first_record) = fetch_record( id )?;
+    fetch_record_latest_with( id, &ReadOptions::default() )
+}
 
-    match first_record.action() {
-	Action::Create(_) => (),
-	_ => Err(UtilsError::NotOriginEntryError(action_hash.to_owned()))?,
-    }
+/// Get the latest Record for any given entity ID using the given [`ReadOptions`]
+pub fn fetch_record_latest_with(id: &EntryHash, options: &ReadOptions) -> UtilsResult<(ActionHash, Record)> {
+    let (origin, record) = fetch_origin_and_latest( id, options, &RecordSource::new( options.strategy )? )?;
+
+    Ok( (origin.action_address().to_owned(), record) )
+}
 
-    let updates = follow_updates( &action_hash, None )?;
+/// Get the Create record and the latest record for any given entity ID
+fn fetch_origin_and_latest(id: &EntryHash, options: &ReadOptions, source: &RecordSource) -> UtilsResult<(Record, Record)> {
+    let (action_hash, origin) = fetch_origin_record_from( id, source )?;
+
+    let updates = follow_updates_from( &action_hash, options, source )?;
     let latest_action_hash = updates.last().unwrap();
-    let record = get( latest_action_hash.to_owned(), GetOptions::latest() )?
+    let record = source.get( latest_action_hash.to_owned() )?
 	.ok_or( UtilsError::ActionNotFoundError(action_hash.to_owned(), Some("".to_string())) )?;
 
-    Ok( (action_hash, record) )
+    Ok( (origin, record) )
 }
 
 
@@ -108,6 +249,7 @@ where
 {
     let entry_hash = hash_entry( entry.to_owned() )?;
     let action_hash = create_entry( entry.to_input() )?;
+    let action = must_get_action( action_hash.to_owned() )?;
 
     Ok(Entity {
 	id: entry_hash.to_owned(),
@@ -115,35 +257,151 @@ where
 	action: action_hash,
 	ctype: entry.get_type(),
 	content: entry.to_owned(),
+	meta: Some( EntityMeta::new( action.action(), action.action() ) ),
+	version: Some( T::version() ),
     })
 }
 
 /// Get an entity by its ID
+///
+/// Fails with [`UtilsError::EntityDeleted`] if the entity has been deleted.
 pub fn get_entity<I,ET>(id: &EntryHash) -> UtilsResult<Entity<I>>
 where
     I: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
     Entry: TryFrom<I, Error = WasmError>,
     ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
 {
-    let (_, record) = fetch_record_latest( id )?;
+    get_entity_with( id, &ReadOptions::default() )
+}
+
+/// Get an entity by its ID using the given [`ReadOptions`]
+pub fn get_entity_with<I,ET>(id: &EntryHash, options: &ReadOptions) -> UtilsResult<Entity<I>>
+where
+    I: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    Entry: TryFrom<I, Error = WasmError>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    get_entity_from( id, options, &RecordSource::new( options.strategy )? )
+}
+
+pub(crate) fn get_entity_from<I,ET>(id: &EntryHash, options: &ReadOptions, source: &RecordSource) -> UtilsResult<Entity<I>>
+where
+    I: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    let status = get_entity_including_deleted_from( id, options, source )?;
+
+    match status.deleted {
+	Some(delete_action) => Err(UtilsError::EntityDeleted(id.to_owned(), delete_action)),
+	None => Ok( status.entity ),
+    }
+}
+
+/// Get an entity by its typed [`EntityId`]
+pub fn get_entity_by_id<I,ET>(id: &EntityId<I,ET>) -> UtilsResult<Entity<I>>
+where
+    I: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    Entry: TryFrom<I, Error = WasmError>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    get_entity( id.hash() )
+}
+
+/// Get an entity by its ID along with its deletion status
+pub fn get_entity_including_deleted<I,ET>(id: &EntryHash) -> UtilsResult<EntityStatus<I>>
+where
+    I: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    Entry: TryFrom<I, Error = WasmError>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    get_entity_including_deleted_with( id, &ReadOptions::default() )
+}
+
+/// Get an entity by its ID along with its deletion status using the given [`ReadOptions`]
+pub fn get_entity_including_deleted_with<I,ET>(id: &EntryHash, options: &ReadOptions) -> UtilsResult<EntityStatus<I>>
+where
+    I: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    Entry: TryFrom<I, Error = WasmError>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    get_entity_including_deleted_from( id, options, &RecordSource::new( options.strategy )? )
+}
+
+fn get_entity_including_deleted_from<I,ET>(id: &EntryHash, options: &ReadOptions, source: &RecordSource) -> UtilsResult<EntityStatus<I>>
+where
+    I: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    let (origin, record) = fetch_origin_and_latest( id, options, source )?;
+    let deleted = find_delete_from( origin.action_address(), source )?;
+
+    Ok(EntityStatus {
+	entity: to_entity( id, origin.action(), record )?,
+	deleted,
+    })
+}
+
+/// Get every version of an entity in update chain order (starting with the Create)
+pub fn get_entity_history<I,ET>(id: &EntryHash) -> UtilsResult<Vec<Entity<I>>>
+where
+    I: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    Entry: TryFrom<I, Error = WasmError>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    get_entity_history_with( id, &ReadOptions::default() )
+}
+
+/// Get every version of an entity using the given [`ReadOptions`]
+pub fn get_entity_history_with<I,ET>(id: &EntryHash, options: &ReadOptions) -> UtilsResult<Vec<Entity<I>>>
+where
+    I: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    Entry: TryFrom<I, Error = WasmError>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    let source = RecordSource::new( options.strategy )?;
+    let (action_hash, origin) = fetch_origin_record_from( id, &source )?;
+
+    follow_updates_from( &action_hash, options, &source )?
+	.into_iter()
+	.map( |addr| {
+	    let record = source.get( addr.to_owned() )?
+		.ok_or( UtilsError::ActionNotFoundError(addr, Some("".to_string())) )?;
+
+	    to_entity( id, origin.action(), record )
+	})
+	.collect()
+}
+
+fn to_entity<I,ET,Id>(id: &Id, origin: &Action, record: Record) -> UtilsResult<Entity<I,Id>>
+where
+    I: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    Id: Clone,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
     let to_type_input = record.to_owned();
     let address = record
 	.action()
 	.entry_hash()
-	.ok_or(UtilsError::EntryNotFoundError(id.to_owned(), None))?;
+	.ok_or(UtilsError::RecordHasNoEntry(record.action_address().to_owned(), record.action().action_type()))?;
 
-    let content : I = to_entry_type( to_type_input )?;
+    let (content, version) = to_entry_type_versioned::<I,ET>( to_type_input )?;
 
     Ok(Entity {
 	id: id.to_owned(),
 	action: record.action_address().to_owned(),
 	address: address.to_owned(),
 	ctype: content.get_type(),
-	content: content,
+	content,
+	meta: Some( EntityMeta::new( origin, record.action() ) ),
+	version,
     })
 }
 
 /// Update an entity
+///
+/// The given address must be the latest version (as found by [`follow_updates`]) or this will fail
+/// with [`UtilsError::StaleUpdate`].  Use [`update_entity_with`] and
+/// [`UpdateOptions::allow_branching`] to intentionally branch from an older version.
 pub fn update_entity<T,I,F,E>(addr: &ActionHash, callback: F) -> UtilsResult<Entity<T>>
 where
     ScopedEntryDefIndex: for<'a> TryFrom<&'a I, Error = WasmError>,
@@ -154,29 +412,176 @@ where
     T: Clone + EntryModel<I>,
     F: FnOnce(T, Record) -> UtilsResult<T>,
 {
-    // TODO: provide automatic check that the given address is the latest one or an optional flag
-    // to indicate the intension to branch from an older update.
-    let id = get_origin_address( &addr )?;
-    let record = get( addr.to_owned(), GetOptions::latest() )?
+    update_entity_with( addr, &UpdateOptions::default(), callback )
+}
+
+/// Update an entity using the given [`UpdateOptions`]
+pub fn update_entity_with<T,I,F,E>(addr: &ActionHash, options: &UpdateOptions, callback: F) -> UtilsResult<Entity<T>>
+where
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a I, Error = WasmError>,
+    Entry: TryFrom<I, Error = E>,
+    Entry: TryFrom<T, Error = E>,
+    WasmError: From<E>,
+    T: TryFrom<Record, Error = WasmError>,
+    T: Clone + EntryModel<I>,
+    F: FnOnce(T, Record) -> UtilsResult<T>,
+{
+    Ok( update_chain_with( addr, options, callback )?.1 )
+}
+
+/// Update an entity only if its current entry is the expected one
+///
+/// The current version is the latest version of the given address's entity (see
+/// [`fetch_record_latest`]) and the update is made on that version.  If its entry is not
+/// `expected_address`, nothing is written and [`ConditionalUpdate::Conflict`] carries the current
+/// entity so the caller can rebase its change.
+pub fn update_entity_if<T,I,F,E>(addr: &ActionHash, expected_address: &EntryHash, callback: F) -> UtilsResult<ConditionalUpdate<T>>
+where
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a I, Error = WasmError>,
+    Entry: TryFrom<I, Error = E>,
+    Entry: TryFrom<T, Error = E>,
+    WasmError: From<E>,
+    T: TryFrom<Record, Error = WasmError>,
+    T: Clone + EntryModel<I>,
+    F: FnOnce(T, Record) -> UtilsResult<T>,
+{
+    update_entity_if_with( addr, expected_address, &UpdateOptions::default(), callback )
+}
+
+/// Update an entity only if its current entry is the expected one using the given
+/// [`UpdateOptions`]
+///
+/// The options' resolver and strategy choose the current version and `author_only` is enforced;
+/// `allow_branching` has no effect.  The update chain is walked once, so an update that lands after
+/// the check creates a branch instead of failing.
+pub fn update_entity_if_with<T,I,F,E>(addr: &ActionHash, expected_address: &EntryHash, options: &UpdateOptions, callback: F) -> UtilsResult<ConditionalUpdate<T>>
+where
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a I, Error = WasmError>,
+    Entry: TryFrom<I, Error = E>,
+    Entry: TryFrom<T, Error = E>,
+    WasmError: From<E>,
+    T: TryFrom<Record, Error = WasmError>,
+    T: Clone + EntryModel<I>,
+    F: FnOnce(T, Record) -> UtilsResult<T>,
+{
+    let id = get_origin_address( addr )?;
+    let read_options = options.read_options();
+    let source = RecordSource::new( read_options.strategy )?;
+    let (origin, record) = fetch_origin_and_latest( &id, &read_options, &source )?;
+
+    if options.author_only {
+	ensure_author( origin.action() )?;
+    }
+
+    let current : Entity<T> = to_entity::<T,I,_>( &id, origin.action(), record.to_owned() )?;
+
+    if &current.address != expected_address {
+	return Ok( ConditionalUpdate::Conflict {
+	    expected: expected_address.to_owned(),
+	    current,
+	} );
+    }
+
+    Ok( ConditionalUpdate::Updated( write_update( id, origin.action(), record, current.content, callback )? ) )
+}
+
+/// Update an entity and return the update chain's Create action along with the updated entity
+fn update_chain_with<T,I,F,E>(addr: &ActionHash, options: &UpdateOptions, callback: F) -> UtilsResult<(ActionHash, Entity<T>)>
+where
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a I, Error = WasmError>,
+    Entry: TryFrom<I, Error = E>,
+    Entry: TryFrom<T, Error = E>,
+    WasmError: From<E>,
+    T: TryFrom<Record, Error = WasmError>,
+    T: Clone + EntryModel<I>,
+    F: FnOnce(T, Record) -> UtilsResult<T>,
+{
+    let chain = trace_action_history( addr )?;
+
+    // The starting 'addr' will always be in the chain so it is safe to unwrap.
+    let (origin_action, id) = chain.last().unwrap().to_owned();
+
+    let origin = must_get_action( origin_action.to_owned() )?;
+
+    if options.author_only {
+	ensure_author( origin.action() )?;
+    }
+
+    let read_options = options.read_options();
+    let source = RecordSource::new( read_options.strategy )?;
+
+    if !options.allow_branching {
+	let updates = follow_updates_from( &origin_action, &read_options, &source )?;
+	let latest = updates.last().unwrap();
+
+	if latest != addr {
+	    Err(UtilsError::StaleUpdate {
+		given: addr.to_owned(),
+		latest: latest.to_owned(),
+	    })?;
+	}
+    }
+
+    let record = source.get( addr.to_owned() )?
 	.ok_or( UtilsError::ActionNotFoundError(addr.to_owned(), Some("Given origin for update is not found".to_string())) )?;
 
     let current : T = to_entry_type( record.clone() )?;
-    let updated_entry = callback( current, record.clone() )?;
+
+    Ok( (origin_action, write_update( id, origin.action(), record, current, callback )? ) )
+}
+
+/// Update the given record with the callback's result
+fn write_update<T,I,F,E>(id: EntryHash, origin: &Action, record: Record, current: T, callback: F) -> UtilsResult<Entity<T>>
+where
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a I, Error = WasmError>,
+    Entry: TryFrom<I, Error = E>,
+    Entry: TryFrom<T, Error = E>,
+    WasmError: From<E>,
+    T: Clone + EntryModel<I>,
+    F: FnOnce(T, Record) -> UtilsResult<T>,
+{
+    let addr = record.action_address().to_owned();
+    let updated_entry = callback( current, record )?;
 
     let entry_hash = hash_entry( updated_entry.to_owned() )?;
-    let action_hash = update_entry( addr.to_owned(), updated_entry.to_input() )?;
+    let action_hash = update_entry( addr, updated_entry.to_input() )?;
+    let action = must_get_action( action_hash.to_owned() )?;
 
     Ok(Entity {
-	id: id,
+	id,
 	action: action_hash,
 	address: entry_hash,
 	ctype: updated_entry.get_type(),
 	content: updated_entry,
+	meta: Some( EntityMeta::new( origin, action.action() ) ),
+	version: Some( T::version() ),
     })
 }
 
 /// Delete an entity
 pub fn delete_entity<T,ET>(id: &EntryHash) -> UtilsResult<ActionHash>
+where
+    T: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    Entry: TryFrom<T, Error = WasmError>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    delete_record::<T,ET>( id, false )
+}
+
+/// Delete an entity only if the caller authored its Create action
+///
+/// Fails with [`UtilsError::NotAuthor`] otherwise.  Use [`delete_entity_with`] and
+/// [`DeleteOptions::author_only`] to also delete links.
+pub fn delete_entity_author_only<T,ET>(id: &EntryHash) -> UtilsResult<ActionHash>
+where
+    T: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    Entry: TryFrom<T, Error = WasmError>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    delete_record::<T,ET>( id, true )
+}
+
+fn delete_record<T,ET>(id: &EntryHash, author_only: bool) -> UtilsResult<ActionHash>
 where
     T: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
     Entry: TryFrom<T, Error = WasmError>,
@@ -184,14 +589,107 @@ where
 {
     let (action_hash, record) = fetch_record( id )?;
     let _ : T = to_entry_type( record )?;
+
+    if author_only {
+	let chain = trace_action_history( &action_hash )?;
+	let origin = must_get_action( chain.last().unwrap().0.to_owned() )?;
+	ensure_author( origin.action() )?;
+    }
+
     let delete_hash = delete_entry( action_hash )?;
 
     Ok( delete_hash )
 }
 
+/// Fail with [`UtilsError::NotAuthor`] unless the calling agent authored the given Create action
+fn ensure_author(origin: &Action) -> UtilsResult<()> {
+    let author = origin.author().to_owned();
+    let caller = agent_info()?.agent_latest_pubkey;
+
+    match author == caller {
+	true => Ok(()),
+	false => Err(UtilsError::NotAuthor { author, caller }),
+    }
+}
+
+/// Delete an entity and the links listed in the given options
+///
+/// The links are deleted in the same zome call so either everything is deleted or nothing is.
+pub fn delete_entity_with<T,LT,ET>(id: &EntryHash, options: &DeleteOptions<LT>) -> UtilsResult<DeleteSummary>
+where
+    T: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    LT: LinkTypeFilterExt + Clone,
+    Entry: TryFrom<T, Error = WasmError>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    let delete_hash = delete_record::<T,ET>( id, options.author_only )?;
+    let target : AnyLinkableHash = id.to_owned().into();
+    let mut removed = Vec::new();
+
+    for link_type in options.outgoing.iter() {
+	for link in get_links( id.to_owned(), link_type.to_owned(), None )? {
+	    removed.push( (id.to_owned(), link) );
+	}
+    }
+
+    for (base, link_type) in options.incoming.iter() {
+	for link in get_links( base.to_owned(), link_type.to_owned(), None )? {
+	    if link.target == target {
+		removed.push( (base.to_owned(), link) );
+	    }
+	}
+    }
+
+    let mut links = Vec::new();
+    for (base, link) in removed {
+	let delete = delete_link( link.create_link_hash.to_owned() )?;
+	links.push( RemovedLink { base, link, delete } );
+    }
+
+    Ok(DeleteSummary {
+	action: delete_hash,
+	links,
+    })
+}
+
 
 /// Get multiple entities for a given base and link tag filter
+///
+/// Links that cannot be resolved are skipped; use [`get_entity_collection`] to find out which.
 pub fn get_entities<T,LT,ET>(id: &EntryHash, link_type: LT, tag: Option<Vec<u8>>) -> UtilsResult<Vec<Entity<T>>>
+where
+    T: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    LT: LinkTypeFilterExt,
+    Entry: TryFrom<T, Error = WasmError>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    get_entities_with( id, link_type, tag, &ReadOptions::default() )
+}
+
+/// Get multiple entities for a given base and link tag filter using the given [`ReadOptions`]
+pub fn get_entities_with<T,LT,ET>(id: &EntryHash, link_type: LT, tag: Option<Vec<u8>>, options: &ReadOptions) -> UtilsResult<Vec<Entity<T>>>
+where
+    T: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    LT: LinkTypeFilterExt,
+    Entry: TryFrom<T, Error = WasmError>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    Ok( get_entity_collection_with( id, link_type, tag, options )?.entities )
+}
+
+/// Get multiple entities for a given base and link tag filter along with the links that failed
+pub fn get_entity_collection<T,LT,ET>(id: &EntryHash, link_type: LT, tag: Option<Vec<u8>>) -> UtilsResult<EntityCollection<T>>
+where
+    T: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    LT: LinkTypeFilterExt,
+    Entry: TryFrom<T, Error = WasmError>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    get_entity_collection_with( id, link_type, tag, &ReadOptions::default() )
+}
+
+/// Get an [`EntityCollection`] using the given [`ReadOptions`]
+pub fn get_entity_collection_with<T,LT,ET>(id: &EntryHash, link_type: LT, tag: Option<Vec<u8>>, options: &ReadOptions) -> UtilsResult<EntityCollection<T>>
 where
     T: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
     LT: LinkTypeFilterExt,
@@ -201,17 +699,1013 @@ where
     let links_result = get_links(
         id.to_owned(),
 	link_type,
-	tag.map( |tag| LinkTag::new( tag ) )
+	tag.map( LinkTag::new )
     );
     debug!("get_entities: {:?}", links_result );
-    let links = links_result?;
 
-    let list = links.into_iter()
-	.filter_map(|link| {
-	    link.target.into_entry_hash()
-		.and_then( |target| get_entity( &target ).ok() )
-	})
-	.collect();
+    resolve_links( links_result?, options )
+}
+
+fn resolve_links<T,ET>(links: Vec<Link>, options: &ReadOptions) -> UtilsResult<EntityCollection<T>>
+where
+    T: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    let source = RecordSource::new( options.strategy )?;
+    let mut collection = EntityCollection {
+	entities: Vec::new(),
+	failures: Vec::new(),
+    };
+
+    for link in links.into_iter() {
+	let result = match link.target.to_owned().into_entry_hash() {
+	    Some(target) => get_entity_from( &target, options, &source ),
+	    None => Err(UtilsError::UnexpectedState(format!("Link target is not an EntryHash: {}", link.target ))),
+	};
+
+	match result {
+	    Ok(entity) => collection.entities.push( entity ),
+	    Err(error) => collection.failures.push( (link, error) ),
+	}
+    }
 
-    Ok(list)
+    Ok( collection )
+}
+
+/// Get the entities linked from the [`Path`] made of the given segments
+///
+/// See [`Entity::link_from_path`].  The path's own links to its child paths are skipped, as are
+/// any other links that cannot be resolved.
+pub fn get_entities_for_path<T,S,LT,ET>(segments: S, link_type: LT) -> UtilsResult<Vec<Entity<T>>>
+where
+    T: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    S: IntoIterator,
+    S::Item: std::fmt::Display,
+    LT: LinkTypeFilterExt,
+    Entry: TryFrom<T, Error = WasmError>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    get_entities_for_path_with( segments, link_type, &ReadOptions::default() )
+}
+
+/// Get the entities linked from the [`Path`] made of the given segments using the given
+/// [`ReadOptions`]
+pub fn get_entities_for_path_with<T,S,LT,ET>(segments: S, link_type: LT, options: &ReadOptions) -> UtilsResult<Vec<Entity<T>>>
+where
+    T: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    S: IntoIterator,
+    S::Item: std::fmt::Display,
+    LT: LinkTypeFilterExt,
+    Entry: TryFrom<T, Error = WasmError>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    Ok( get_entity_collection_for_path_with( segments, link_type, options )?.entities )
+}
+
+/// Get an [`EntityCollection`] for the [`Path`] made of the given segments
+///
+/// The path's own links to its child paths are not entities, so they are left out of the
+/// collection instead of being reported as failures.
+pub fn get_entity_collection_for_path<T,S,LT,ET>(segments: S, link_type: LT) -> UtilsResult<EntityCollection<T>>
+where
+    T: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    S: IntoIterator,
+    S::Item: std::fmt::Display,
+    LT: LinkTypeFilterExt,
+    Entry: TryFrom<T, Error = WasmError>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    get_entity_collection_for_path_with( segments, link_type, &ReadOptions::default() )
+}
+
+/// Get an [`EntityCollection`] for the [`Path`] made of the given segments using the given
+/// [`ReadOptions`]
+pub fn get_entity_collection_for_path_with<T,S,LT,ET>(segments: S, link_type: LT, options: &ReadOptions) -> UtilsResult<EntityCollection<T>>
+where
+    T: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    S: IntoIterator,
+    S::Item: std::fmt::Display,
+    LT: LinkTypeFilterExt,
+    Entry: TryFrom<T, Error = WasmError>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    let path = path_from_collection( segments )?;
+    let links = get_links( path.path_entry_hash()?, link_type, None )?;
+
+    let mut entity_links = Vec::new();
+    for link in links.into_iter() {
+	if !is_child_path_link( &path, &link )? {
+	    entity_links.push( link );
+	}
+    }
+
+    resolve_links( entity_links, options )
+}
+
+/// Check if the link is one made by [`TypedPath::ensure`] from the given path to a child path
+///
+/// Child path links are tagged with the child's last component (see [`Path::make_tag`]) and
+/// target the child path's entry.
+fn is_child_path_link(path: &Path, link: &Link) -> UtilsResult<bool> {
+    let bytes = SerializedBytes::from( UnsafeBytes::from( link.tag.to_owned().into_inner() ) );
+    let component = match hdk::hash_path::path::Component::try_from( bytes ) {
+	Ok(component) => component,
+	Err(_) => return Ok( false ),
+    };
+
+    let mut child = path.to_owned();
+    child.append_component( component );
+
+    Ok( link.target == child.path_entry_hash()?.into() )
+}
+
+/// Get multiple entities for a given base along with their decoded link tags
+///
+/// `tag_prefix` filters the links by the start of their tag; pass a tuple of the leading tag
+/// fields (eg. `Some( &("rust",) )`), which is encoded with [`encode_tag`].  Links whose tag cannot
+/// be decoded as `Tag` or whose target cannot be resolved are skipped.
+pub fn get_entities_tagged<T,Tag,P,LT,ET>(id: &EntryHash, link_type: LT, tag_prefix: Option<&P>) -> UtilsResult<Vec<(Entity<T>, Tag)>>
+where
+    T: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    Tag: serde::de::DeserializeOwned,
+    P: Serialize + ?Sized,
+    LT: LinkTypeFilterExt,
+    Entry: TryFrom<T, Error = WasmError>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    get_entities_tagged_with( id, link_type, tag_prefix, &ReadOptions::default() )
+}
+
+/// Get multiple entities along with their decoded link tags using the given [`ReadOptions`]
+pub fn get_entities_tagged_with<T,Tag,P,LT,ET>(id: &EntryHash, link_type: LT, tag_prefix: Option<&P>, options: &ReadOptions) -> UtilsResult<Vec<(Entity<T>, Tag)>>
+where
+    T: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    Tag: serde::de::DeserializeOwned,
+    P: Serialize + ?Sized,
+    LT: LinkTypeFilterExt,
+    Entry: TryFrom<T, Error = WasmError>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    let tag_prefix = match tag_prefix {
+	Some(prefix) => Some( encode_tag( prefix )? ),
+	None => None,
+    };
+    let source = RecordSource::new( options.strategy )?;
+    let links = get_links(
+        id.to_owned(),
+	link_type,
+	tag_prefix
+    )?;
+
+    let mut items = Vec::new();
+
+    for link in links_after( links, None ) {
+	let tag = match decode_tag( &link.tag ) {
+	    Ok(tag) => tag,
+	    Err(error) => {
+		debug!("Skipping link {} with undecodable tag: {}", link.create_link_hash, error );
+		continue;
+	    },
+	};
+
+	if let Some(entity) = link.target.into_entry_hash()
+	    .and_then( |target| get_entity_from( &target, options, &source ).ok() )
+	{
+	    items.push( (entity, tag) );
+	}
+    }
+
+    Ok( items )
+}
+
+/// Get a page of entities for a given base and link tag filter
+///
+/// Links are ordered by timestamp (oldest first) and only the targets of the returned page are
+/// fetched.  Pass the returned [`Page::next`] cursor to get the following page.  A `limit` of
+/// zero is rejected with [`UtilsError::InvalidPageLimit`].
+pub fn get_entities_page<T,LT,ET>(id: &EntryHash, link_type: LT, tag: Option<Vec<u8>>, limit: usize, cursor: Option<&LinkCursor>) -> UtilsResult<Page<Entity<T>>>
+where
+    T: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    LT: LinkTypeFilterExt,
+    Entry: TryFrom<T, Error = WasmError>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    get_entities_page_with( id, link_type, tag, limit, cursor, &ReadOptions::default() )
+}
+
+/// Get a page of entities using the given [`ReadOptions`]
+pub fn get_entities_page_with<T,LT,ET>(id: &EntryHash, link_type: LT, tag: Option<Vec<u8>>, limit: usize, cursor: Option<&LinkCursor>, options: &ReadOptions) -> UtilsResult<Page<Entity<T>>>
+where
+    T: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    LT: LinkTypeFilterExt,
+    Entry: TryFrom<T, Error = WasmError>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    if limit == 0 {
+	return Err( UtilsError::InvalidPageLimit );
+    }
+
+    let links = get_links(
+        id.to_owned(),
+	link_type,
+	tag.map( LinkTag::new )
+    )?;
+    let links = links_after( links, cursor );
+    let total = links.len();
+    let source = RecordSource::new( options.strategy )?;
+
+    let mut items = Vec::new();
+    let mut consumed = 0;
+
+    for link in links.iter() {
+	if items.len() >= limit {
+	    break;
+	}
+	consumed += 1;
+
+	if let Some(entity) = link.target.to_owned().into_entry_hash()
+	    .and_then( |target| get_entity_from( &target, options, &source ).ok() )
+	{
+	    items.push( entity );
+	}
+    }
+
+    let next = match consumed < total {
+	true => Some( LinkCursor::from_link( &links[ consumed - 1 ] ) ),
+	false => None,
+    };
+
+    Ok(Page {
+	items,
+	next,
+    })
+}
+
+
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::mock::MockDht;
+
+    #[hdk_entry_helper]
+    #[derive(Clone)]
+    pub struct PostEntry {
+	pub message: String,
+    }
+
+    impl EntryModel<EntryTypes> for PostEntry {
+	fn name() -> &'static str { "Post" }
+	fn get_type(&self) -> EntityType {
+	    EntityType::new( "post", "entry" )
+	}
+	fn to_input(&self) -> EntryTypes {
+	    EntryTypes::Post(self.clone())
+	}
+	fn version() -> u32 { 2 }
+	fn upgrades() -> Vec<Upgrade<Self>> {
+	    vec![
+		Upgrade::new( 1, |old: LegacyPostEntry| post( &old.text ) ),
+	    ]
+	}
+    }
+
+    /// The version 1 shape of [`PostEntry`]
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct LegacyPostEntry {
+	pub text: String,
+    }
+
+    #[hdk_entry_helper]
+    #[derive(Clone)]
+    pub struct CommentEntry {
+	pub message: String,
+    }
+
+    impl EntryModel<EntryTypes> for CommentEntry {
+	fn name() -> &'static str { "Comment" }
+	fn get_type(&self) -> EntityType {
+	    EntityType::new( "comment", "entry" )
+	}
+	fn to_input(&self) -> EntryTypes {
+	    EntryTypes::Comment(self.clone())
+	}
+	fn version() -> u32 { 3 }
+	fn upgrades() -> Vec<Upgrade<Self>> {
+	    // Declared oldest first to check that upgrades are tried from the highest version down
+	    vec![
+		Upgrade::new( 1, |old: LegacyCommentEntry| comment( &format!("v1 {}", old.body ) ) ),
+		Upgrade::new( 2, |old: EditedCommentEntry| comment( &format!("v2 {}", old.body ) ) ),
+	    ]
+	}
+    }
+
+    /// The version 1 shape of [`CommentEntry`]
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct LegacyCommentEntry {
+	pub body: String,
+    }
+
+    /// The version 2 shape of [`CommentEntry`]
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct EditedCommentEntry {
+	pub body: String,
+	pub edited: bool,
+    }
+
+    #[hdk_entry_defs(skip_hdk_extern = true)]
+    #[unit_enum(UnitEntryTypes)]
+    pub enum EntryTypes {
+	#[entry_def]
+	Post(PostEntry),
+	#[entry_def]
+	Comment(CommentEntry),
+    }
+
+    #[hdk_link_types(skip_no_mangle = true)]
+    pub enum LinkTypes {
+	Post,
+	Comment,
+    }
+
+    pub fn setup() -> MockDht {
+	let dht = MockDht::new( 2, 2 );
+	dht.install();
+	dht
+    }
+
+    pub fn post(message: &str) -> PostEntry {
+	PostEntry {
+	    message: message.to_string(),
+	}
+    }
+
+    pub fn comment(message: &str) -> CommentEntry {
+	CommentEntry {
+	    message: message.to_string(),
+	}
+    }
+
+    fn edit(message: &str) -> impl FnOnce(PostEntry, Record) -> UtilsResult<PostEntry> + '_ {
+	move |mut previous: PostEntry, _| {
+	    previous.message = message.to_string();
+	    Ok( previous )
+	}
+    }
+
+    #[test]
+    fn crud_test() {
+	setup();
+
+	let created = create_entity( &post("Hello, world!") ).unwrap();
+	let fetched : Entity<PostEntry> = get_entity( &created.id ).unwrap();
+
+	assert_eq!( fetched.action, created.action );
+	assert_eq!( fetched.content.message, "Hello, world!" );
+
+	let updated = update_entity( &created.action, edit("Goodbye, world!") ).unwrap();
+	let fetched : Entity<PostEntry> = get_entity( &created.id ).unwrap();
+
+	assert_eq!( updated.id, created.id );
+	assert_eq!( fetched.action, updated.action );
+	assert_eq!( fetched.content.message, "Goodbye, world!" );
+
+	let error = get_entity::<CommentEntry,EntryTypes>( &created.id ).unwrap_err();
+	assert!( matches!( error, UtilsError::WrongEntryTypeError(..) ), "{:?}", error );
+
+	let error = get_entity::<PostEntry,EntryTypes>( &updated.address ).unwrap_err();
+	assert!( matches!( error, UtilsError::NotOriginEntryError(_) ), "{:?}", error );
+
+	let delete_hash = delete_entity::<PostEntry,EntryTypes>( &created.id ).unwrap();
+	let error = get_entity::<PostEntry,EntryTypes>( &created.id ).unwrap_err();
+	assert!( matches!( error, UtilsError::EntityDeleted(_, ref hash) if *hash == delete_hash ), "{:?}", error );
+
+	let status = get_entity_including_deleted::<PostEntry,EntryTypes>( &created.id ).unwrap();
+	assert_eq!( status.deleted, Some(delete_hash) );
+	assert_eq!( status.entity.content.message, "Goodbye, world!" );
+    }
+
+    #[test]
+    fn update_chain_test() {
+	let dht = setup();
+
+	let created = create_entity( &post("v1") ).unwrap();
+	let v2 = update_entity( &created.action, edit("v2") ).unwrap();
+	let v3 = update_entity( &v2.action, edit("v3") ).unwrap();
+
+	let chain = follow_updates( &created.action, None ).unwrap();
+	assert_eq!( chain, vec![ created.action.clone(), v2.action.clone(), v3.action.clone() ] );
+
+	let history = get_entity_history::<PostEntry,EntryTypes>( &created.id ).unwrap();
+	let messages : Vec<String> = history.iter().map( |entity| entity.content.message.to_owned() ).collect();
+	assert_eq!( messages, vec![ "v1", "v2", "v3" ] );
+	assert!( history[0].meta.as_ref().unwrap().updated_at < history[2].meta.as_ref().unwrap().updated_at );
+
+	assert_eq!( get_origin_address( &v3.action ).unwrap(), created.id );
+
+	let error = update_entity( &v2.action, edit("stale") ).unwrap_err();
+	assert!( matches!( error, UtilsError::StaleUpdate { ref latest, .. } if *latest == v3.action ), "{:?}", error );
+
+	let options = ReadOptions {
+	    max_depth: 2,
+	    ..Default::default()
+	};
+	let error = follow_updates_with( &created.action, &options ).unwrap_err();
+	assert!( matches!( error, UtilsError::HistoryTooDeep(_, 2) ), "{:?}", error );
+
+	let error = trace_action_history_with( &v3.action, &options ).unwrap_err();
+	assert!( matches!( error, UtilsError::HistoryTooDeep(_, 2) ), "{:?}", error );
+
+	assert_eq!( dht.action_count(), 3 );
+    }
+
+    #[test]
+    fn fork_test() {
+	let dht = setup();
+	let alice = dht.agent();
+	let bobby = AgentPubKey::from_raw_32( vec![2; 32] );
+
+	let created = create_entity( &post("original") ).unwrap();
+
+	dht.set_agent( bobby );
+	let by_bobby = update_entity( &created.action, edit("by bobby") ).unwrap();
+
+	dht.set_agent( alice );
+	let branch = update_entity_with( &created.action, &UpdateOptions::branching(), edit("by alice") ).unwrap();
+
+	let error = update_entity( &created.action, edit("stale") ).unwrap_err();
+	assert!( matches!( error, UtilsError::StaleUpdate { .. } ), "{:?}", error );
+
+	let fetched : Entity<PostEntry> = get_entity( &created.id ).unwrap();
+	assert_eq!( fetched.action, by_bobby.action );
+
+	let options = ReadOptions::with_resolver( &OriginalAuthorUpdate );
+	let fetched : Entity<PostEntry> = get_entity_with( &created.id, &options ).unwrap();
+	assert_eq!( fetched.action, branch.action );
+
+	let options = ReadOptions::with_resolver( &LatestUpdate );
+	let fetched : Entity<PostEntry> = get_entity_with( &created.id, &options ).unwrap();
+	assert_eq!( fetched.action, branch.action );
+
+	let error = update_entity( &branch.action, edit("stale branch") ).unwrap_err();
+	assert!( matches!( error, UtilsError::StaleUpdate { .. } ), "{:?}", error );
+
+	let options = UpdateOptions::with_resolver( &LatestUpdate );
+	let updated = update_entity_with( &branch.action, &options, edit("latest branch") ).unwrap();
+
+	let options = ReadOptions::with_resolver( &LatestUpdate );
+	let fetched : Entity<PostEntry> = get_entity_with( &created.id, &options ).unwrap();
+	assert_eq!( fetched.action, updated.action );
+    }
+
+    #[test]
+    fn links_test() {
+	setup();
+
+	let post_a = create_entity( &post("A") ).unwrap();
+	let post_b = create_entity( &post("B") ).unwrap();
+	let comment_1 = create_entity( &comment("1") ).unwrap();
+	let comment_2 = create_entity( &comment("2") ).unwrap();
+
+	comment_1.link_from( &post_a.id, LinkTypes::Comment, None ).unwrap();
+	comment_2.link_from( &post_a.id, LinkTypes::Comment, None ).unwrap();
+	post_b.link_from( &post_a.id, LinkTypes::Comment, None ).unwrap();
+
+	let comments : Vec<Entity<CommentEntry>> = get_entities( &post_a.id, LinkTypes::Comment, None ).unwrap();
+	assert_eq!( comments.len(), 2 );
+
+	let collection : EntityCollection<CommentEntry> = get_entity_collection( &post_a.id, LinkTypes::Comment, None ).unwrap();
+	assert_eq!( collection.entities.len(), 2 );
+	assert_eq!( collection.failures.len(), 1 );
+	assert_eq!( collection.failures[0].0.target, post_b.id.clone().into() );
+
+	let json = serde_json::to_value( &collection ).unwrap();
+	assert_eq!( json["entities"].as_array().unwrap().len(), 2 );
+	assert_eq!( json["failures"][0][1]["kind"], "WrongEntryType" );
+
+	let page : Page<Entity<CommentEntry>> = get_entities_page( &post_a.id, LinkTypes::Comment, None, 1, None ).unwrap();
+	assert_eq!( page.items.len(), 1 );
+	assert_eq!( page.items[0].id, comment_1.id );
+
+	let page : Page<Entity<CommentEntry>> = get_entities_page( &post_a.id, LinkTypes::Comment, None, 1, page.next.as_ref() ).unwrap();
+	assert_eq!( page.items[0].id, comment_2.id );
+	assert!( page.next.is_some() );
+
+	let page : Page<Entity<CommentEntry>> = get_entities_page( &post_a.id, LinkTypes::Comment, None, 1, page.next.as_ref() ).unwrap();
+	assert_eq!( page.items.len(), 0 );
+	assert!( page.next.is_none() );
+
+	let result : UtilsResult<Page<Entity<CommentEntry>>> = get_entities_page( &post_a.id, LinkTypes::Comment, None, 0, None );
+	assert!( matches!( result, Err(UtilsError::InvalidPageLimit) ) );
+
+	comment_1.move_link_from( LinkTypes::Comment, None, &post_a.id, &post_b.id ).unwrap();
+
+	let comments : Vec<Entity<CommentEntry>> = get_entities( &post_a.id, LinkTypes::Comment, None ).unwrap();
+	assert_eq!( comments.len(), 1 );
+	assert_eq!( comments[0].id, comment_2.id );
+
+	let comments : Vec<Entity<CommentEntry>> = get_entities( &post_b.id, LinkTypes::Comment, None ).unwrap();
+	assert_eq!( comments.len(), 1 );
+	assert_eq!( comments[0].id, comment_1.id );
+
+	delete_entity::<CommentEntry,EntryTypes>( &comment_2.id ).unwrap();
+
+	let collection : EntityCollection<CommentEntry> = get_entity_collection( &post_a.id, LinkTypes::Comment, None ).unwrap();
+	assert_eq!( collection.entities.len(), 0 );
+	assert!( matches!( collection.failures[0].1, UtilsError::EntityDeleted(..) ) );
+    }
+
+    #[test]
+    fn tagged_links_test() {
+	setup();
+
+	let post_a = create_entity( &post("A") ).unwrap();
+	let comment_1 = create_entity( &comment("1") ).unwrap();
+	let comment_2 = create_entity( &comment("2") ).unwrap();
+	let comment_3 = create_entity( &comment("3") ).unwrap();
+
+	comment_1.link_from_tagged( &post_a.id, LinkTypes::Comment, &("rust".to_string(), 2u32) ).unwrap();
+	comment_2.link_from_tagged( &post_a.id, LinkTypes::Comment, &("rusty".to_string(), 1u32) ).unwrap();
+	comment_3.link_from( &post_a.id, LinkTypes::Comment, Some( vec![ 0xFF ] ) ).unwrap();
+
+	let comments : Vec<(Entity<CommentEntry>, (String, u32))> = get_entities_tagged( &post_a.id, LinkTypes::Comment, Some( &("rust",) ) ).unwrap();
+	assert_eq!( comments.len(), 1 );
+	assert_eq!( comments[0].0.id, comment_1.id );
+	assert_eq!( comments[0].1, ("rust".to_string(), 2) );
+
+	let comments : Vec<(Entity<CommentEntry>, (String, u32))> = get_entities_tagged( &post_a.id, LinkTypes::Comment, None::<&()> ).unwrap();
+	assert_eq!( comments.len(), 2 );
+    }
+
+    #[test]
+    fn bidirectional_links_test() {
+	setup();
+
+	let post_a = create_entity( &post("A") ).unwrap();
+	let comment_1 = create_entity( &comment("1") ).unwrap();
+
+	comment_1.link_bidirectional( &post_a.id, LinkTypes::Post, LinkTypes::Comment, None ).unwrap();
+
+	let posts : Vec<Entity<PostEntry>> = get_entities( &comment_1.id, LinkTypes::Post, None ).unwrap();
+	assert_eq!( posts.len(), 1 );
+	assert_eq!( posts[0].id, post_a.id );
+
+	let comments : Vec<Entity<CommentEntry>> = get_entities( &post_a.id, LinkTypes::Comment, None ).unwrap();
+	assert_eq!( comments.len(), 1 );
+	assert_eq!( comments[0].id, comment_1.id );
+
+	let deleted = comment_1.unlink_bidirectional( &post_a.id, LinkTypes::Post, LinkTypes::Comment, None ).unwrap();
+	assert_eq!( deleted.len(), 2 );
+
+	let posts : Vec<Entity<PostEntry>> = get_entities( &comment_1.id, LinkTypes::Post, None ).unwrap();
+	let comments : Vec<Entity<CommentEntry>> = get_entities( &post_a.id, LinkTypes::Comment, None ).unwrap();
+	assert!( posts.is_empty() && comments.is_empty() );
+    }
+
+    #[test]
+    fn unlink_test() {
+	setup();
+
+	let post_a = create_entity( &post("A") ).unwrap();
+	let comment_1 = create_entity( &comment("1") ).unwrap();
+	let comment_2 = create_entity( &comment("2") ).unwrap();
+
+	let first = comment_1.link_from( &post_a.id, LinkTypes::Comment, None ).unwrap();
+	comment_1.link_from( &post_a.id, LinkTypes::Comment, None ).unwrap();
+	comment_2.link_from( &post_a.id, LinkTypes::Comment, None ).unwrap();
+	post_a.link_to( &comment_2.id, LinkTypes::Post, None ).unwrap();
+
+	let deleted = comment_1.unlink_from_with( &post_a.id, LinkTypes::Comment, None, &UnlinkOptions::first_only() ).unwrap();
+	assert_eq!( deleted.len(), 1 );
+
+	let links = get_link_details( post_a.id.clone(), LinkTypes::Comment, None ).unwrap().into_inner();
+	let (_, deletes) = links.iter().find( |(create, _)| *create.action_address() == first ).unwrap();
+	assert_eq!( *deletes[0].action_address(), deleted[0] );
+
+	let deleted = comment_1.unlink_from( &post_a.id, LinkTypes::Comment, None ).unwrap();
+	assert_eq!( deleted.len(), 1 );
+
+	let deleted = comment_1.unlink_from( &post_a.id, LinkTypes::Comment, None ).unwrap();
+	assert!( deleted.is_empty() );
+
+	let comments : Vec<Entity<CommentEntry>> = get_entities( &post_a.id, LinkTypes::Comment, None ).unwrap();
+	assert_eq!( comments.len(), 1 );
+	assert_eq!( comments[0].id, comment_2.id );
+
+	let deleted = post_a.unlink_to( &comment_2.id, LinkTypes::Post, None ).unwrap();
+	assert_eq!( deleted.len(), 1 );
+    }
+
+    #[test]
+    fn move_link_to_test() {
+	setup();
+
+	let post_a = create_entity( &post("A") ).unwrap();
+	let comment_1 = create_entity( &comment("1") ).unwrap();
+	let comment_2 = create_entity( &comment("2") ).unwrap();
+	let comment_3 = create_entity( &comment("3") ).unwrap();
+
+	post_a.link_to( &comment_1.id, LinkTypes::Comment, None ).unwrap();
+	let existing = post_a.link_to( &comment_3.id, LinkTypes::Comment, None ).unwrap();
+
+	post_a.move_link_to( LinkTypes::Comment, None, &comment_1.id, &comment_2.id ).unwrap();
+
+	let comments : Vec<Entity<CommentEntry>> = get_entities( &post_a.id, LinkTypes::Comment, None ).unwrap();
+	let ids : Vec<EntryHash> = comments.into_iter().map( |entity| entity.id ).collect();
+	assert_eq!( ids, vec![ comment_3.id.clone(), comment_2.id.clone() ] );
+
+	let reused = post_a.move_link_to( LinkTypes::Comment, None, &comment_2.id, &comment_3.id ).unwrap();
+	assert_eq!( reused, existing );
+
+	let error = post_a.move_link_to( LinkTypes::Comment, None, &comment_1.id, &comment_2.id ).unwrap_err();
+	assert!( matches!( error, UtilsError::UnexpectedState(_) ), "{:?}", error );
+
+	let relinked = post_a.move_link_to( LinkTypes::Comment, None, &comment_3.id, &comment_3.id ).unwrap();
+	assert_ne!( relinked, existing );
+
+	let comments : Vec<Entity<CommentEntry>> = get_entities( &post_a.id, LinkTypes::Comment, None ).unwrap();
+	let ids : Vec<EntryHash> = comments.into_iter().map( |entity| entity.id ).collect();
+	assert_eq!( ids, vec![ comment_3.id.clone() ] );
+    }
+
+    #[test]
+    fn delete_entity_with_test() {
+	setup();
+
+	let post_a = create_entity( &post("A") ).unwrap();
+	let post_b = create_entity( &post("B") ).unwrap();
+	let comment_1 = create_entity( &comment("1") ).unwrap();
+	let comment_2 = create_entity( &comment("2") ).unwrap();
+
+	comment_1.link_from( &post_a.id, LinkTypes::Comment, None ).unwrap();
+	comment_2.link_from( &post_a.id, LinkTypes::Comment, None ).unwrap();
+	comment_1.link_from( &post_b.id, LinkTypes::Comment, None ).unwrap();
+	comment_1.link_to( &post_a.id, LinkTypes::Post, None ).unwrap();
+
+	let options = DeleteOptions {
+	    outgoing: vec![ LinkTypes::Post ],
+	    incoming: vec![ (post_a.id.clone(), LinkTypes::Comment) ],
+	    ..Default::default()
+	};
+	let summary = delete_entity_with::<CommentEntry,_,EntryTypes>( &comment_1.id, &options ).unwrap();
+
+	assert_eq!( summary.links.len(), 2 );
+	assert_eq!( summary.links[0].base, comment_1.id );
+	assert_eq!( summary.links[0].link.target, post_a.id.clone().into() );
+	assert_eq!( summary.links[1].base, post_a.id );
+	assert_eq!( summary.links[1].link.target, comment_1.id.clone().into() );
+
+	let error = get_entity::<CommentEntry,EntryTypes>( &comment_1.id ).unwrap_err();
+	assert!( matches!( error, UtilsError::EntityDeleted(_, ref hash) if *hash == summary.action ), "{:?}", error );
+
+	let collection : EntityCollection<CommentEntry> = get_entity_collection( &post_a.id, LinkTypes::Comment, None ).unwrap();
+	assert!( collection.is_complete() );
+	assert_eq!( collection.entities.len(), 1 );
+
+	// Bases that were not listed are left alone
+	let collection : EntityCollection<CommentEntry> = get_entity_collection( &post_b.id, LinkTypes::Comment, None ).unwrap();
+	assert_eq!( collection.failures.len(), 1 );
+    }
+
+    #[test]
+    fn path_collection_test() {
+	setup();
+
+	let post_a = create_entity( &post("A") ).unwrap();
+	let post_b = create_entity( &post("B") ).unwrap();
+
+	post_a.link_from_path( vec![ "posts" ], LinkTypes::Post, None ).unwrap();
+	post_b.link_from_path( vec![ "posts", "rust" ], LinkTypes::Post, None ).unwrap();
+
+	let posts : Vec<Entity<PostEntry>> = get_entities_for_path( vec![ "posts" ], LinkTypes::Post ).unwrap();
+	assert_eq!( posts.len(), 1 );
+	assert_eq!( posts[0].id, post_a.id );
+
+	let posts : Vec<Entity<PostEntry>> = get_entities_for_path( vec![ "posts", "rust" ], LinkTypes::Post ).unwrap();
+	assert_eq!( posts.len(), 1 );
+	assert_eq!( posts[0].id, post_b.id );
+
+	let posts : Vec<Entity<PostEntry>> = get_entities_for_path( vec![ "posts", "go" ], LinkTypes::Post ).unwrap();
+	assert!( posts.is_empty() );
+
+	let collection : EntityCollection<PostEntry> = get_entity_collection_for_path( vec![ "posts" ], LinkTypes::Post ).unwrap();
+	assert_eq!( collection.entities.len(), 1 );
+	assert!( collection.is_complete(), "{:?}", collection.failures );
+
+	let base = path_from_collection( vec![ "posts" ] ).unwrap().path_entry_hash().unwrap();
+	let collection : EntityCollection<PostEntry> = get_entity_collection( &base, LinkTypes::Post, None ).unwrap();
+	assert_eq!( collection.entities.len(), 1 );
+	assert_eq!( collection.failures.len(), 1 );
+    }
+
+    #[test]
+    fn time_index_test() {
+	setup();
+
+	let index = TimeIndex::new( "activity", LinkTypes::Post, TimeBucket::Hour );
+	let day = 86_400_000;
+	let start = 1_704_025_800_000; // 2023-12-31T12:30:00Z
+
+	let mut posts = Vec::new();
+	for (i, offset) in [ 0, 1, day, day + 1, 40 * day ].iter().enumerate() {
+	    let entity = create_entity( &post( &i.to_string() ) ).unwrap();
+	    entity.link_from_time_index_at( &index, start + offset ).unwrap();
+	    posts.push( entity.id );
+	}
+
+	let ids = |entities: Vec<Entity<PostEntry>>| -> Vec<EntryHash> {
+	    entities.into_iter().map( |entity| entity.id ).collect()
+	};
+
+	let found = get_entities_in_range( &index, start, start + 50 * day, 10 ).unwrap();
+	assert_eq!( ids( found ), posts );
+
+	let found = get_entities_in_range( &index, start + 50 * day, start, 3 ).unwrap();
+	assert_eq!( ids( found ), vec![ posts[4].clone(), posts[3].clone(), posts[2].clone() ] );
+
+	let found = get_entities_in_range( &index, start + 1, start + day, 10 ).unwrap();
+	assert_eq!( ids( found ), vec![ posts[1].clone(), posts[2].clone() ] );
+
+	let found : Vec<Entity<PostEntry>> = get_entities_in_range( &index, 0, start - 1, 10 ).unwrap();
+	assert!( found.is_empty() );
+    }
+
+    #[test]
+    fn entity_id_test() {
+	setup();
+
+	let created = create_entity( &post("Hello") ).unwrap();
+	let reply = create_entity( &comment("Hi") ).unwrap();
+
+	let id : EntityId<PostEntry, EntryTypes> = created.entity_id();
+	let text = id.to_string();
+	assert_eq!( text, format!("post:{}", created.id ) );
+
+	let parsed : EntityId<PostEntry, EntryTypes> = text.parse().unwrap();
+	assert_eq!( parsed, id );
+	assert_eq!( get_entity_by_id( &parsed ).unwrap().action, created.action );
+
+	let encoded = holochain_serialized_bytes::encode( &id ).unwrap();
+	assert_eq!( encoded, holochain_serialized_bytes::encode( &created.id ).unwrap() );
+
+	let comment_id : EntityId<CommentEntry, EntryTypes> = reply.entity_id();
+	let error = comment_id.to_string().parse::<EntityId<PostEntry, EntryTypes>>().unwrap_err();
+	assert!( matches!( error, UtilsError::EntityIdTypeMismatch { ref expected, ref found } if expected == "post" && found == "comment" ), "{:?}", error );
+
+	let error = created.id.to_string().parse::<EntityId<PostEntry, EntryTypes>>().unwrap_err();
+	assert!( matches!( error, UtilsError::InvalidEntityId(..) ), "{:?}", error );
+
+	let error = "post:uhCEkNotAHash".parse::<EntityId<PostEntry, EntryTypes>>().unwrap_err();
+	assert!( matches!( error, UtilsError::InvalidEntityId(..) ), "{:?}", error );
+    }
+
+    #[test]
+    fn update_entity_if_test() {
+	let dht = setup();
+
+	let created = create_entity( &post("v1") ).unwrap();
+	let v2 = update_entity( &created.action, edit("v2") ).unwrap();
+
+	let v3 = update_entity_if( &created.action, &v2.address, edit("v3") ).unwrap().into_result().unwrap();
+	assert_eq!( v3.id, created.id );
+	assert_eq!( follow_updates( &created.action, None ).unwrap().last(), Some(&v3.action) );
+
+	let action_count = dht.action_count();
+	let result = update_entity_if( &v2.action, &v2.address, edit("v4") ).unwrap();
+	assert!( matches!( result, ConditionalUpdate::Conflict { ref expected, ref current } if *expected == v2.address && current.action == v3.action ), "{:?}", result );
+	assert_eq!( dht.action_count(), action_count );
+
+	if let ConditionalUpdate::Conflict { current, .. } = &result {
+	    assert_eq!( current.content.message, "v3" );
+	}
+
+	let error = result.into_result().unwrap_err();
+	assert!( matches!( error, UtilsError::ContentConflict { ref expected, ref found } if *expected == v2.address && *found == v3.address ), "{:?}", error );
+
+	let payload = error.payload();
+	assert_eq!( payload.kind, "ContentConflict" );
+	assert_eq!( payload.data["found"], v3.address.to_string() );
+
+	let fetched : Entity<PostEntry> = get_entity( &created.id ).unwrap();
+	assert_eq!( fetched.action, v3.action );
+
+	dht.set_agent( AgentPubKey::from_raw_32( vec![2; 32] ) );
+	let error = update_entity_if_with( &created.action, &v3.address, &UpdateOptions::author_only(), edit("v4") ).unwrap_err();
+	assert!( matches!( error, UtilsError::NotAuthor { .. } ), "{:?}", error );
+    }
+
+    #[test]
+    fn update_entity_if_concurrent_test() {
+	setup();
+
+	let created = create_entity( &post("v1") ).unwrap();
+
+	// Another update lands after the check but before the write
+	let result = update_entity_if( &created.action, &created.address, |mut previous: PostEntry, _| {
+	    update_entity( &created.action, edit("concurrent") )?;
+	    previous.message = "checked".to_string();
+	    Ok( previous )
+	}).unwrap();
+
+	let updated = match result {
+	    ConditionalUpdate::Updated(entity) => entity,
+	    conflict => panic!("Expected an update; found {:?}", conflict ),
+	};
+	let update = match must_get_action( updated.action.to_owned() ).unwrap().action() {
+	    Action::Update(update) => update.to_owned(),
+	    action => panic!("Expected an Update action; found {:?}", action ),
+	};
+	assert_eq!( update.original_action_address, created.action );
+
+	let details = get_details( created.action.to_owned(), GetOptions::latest() ).unwrap();
+	assert!( matches!( details, Some(Details::Record(ref details)) if details.updates.len() == 2 ), "{:?}", details );
+    }
+
+    /// Commit content with any shape as the given entry type
+    fn create_raw<C: Serialize + std::fmt::Debug>(content: &C, entry_def_index: u8) -> (EntryHash, ActionHash) {
+	let bytes = holochain_serialized_bytes::encode( content ).unwrap();
+	let entry = Entry::App(AppEntryBytes(SerializedBytes::from( UnsafeBytes::from( bytes ) )));
+	let id = hash_entry( entry.to_owned() ).unwrap();
+	let location = AppEntryDefLocation {
+	    zome_index: 0.into(),
+	    entry_def_index: entry_def_index.into(),
+	};
+	let action = create( CreateInput::new( EntryDefLocation::App(location), EntryVisibility::Public, entry, ChainTopOrdering::default() ) ).unwrap();
+
+	(id, action)
+    }
+
+    #[test]
+    fn versioned_model_test() {
+	setup();
+
+	let (id, action) = create_raw( &LegacyPostEntry { text: "old".to_string() }, 0 );
+
+	let fetched : Entity<PostEntry> = get_entity( &id ).unwrap();
+	assert_eq!( fetched.content.message, "old" );
+	assert_eq!( fetched.version, Some(1) );
+
+	let reply = create_entity( &comment("reply") ).unwrap();
+	fetched.link_from( &reply.id, LinkTypes::Post, None ).unwrap();
+	let posts : Vec<Entity<PostEntry>> = get_entities( &reply.id, LinkTypes::Post, None ).unwrap();
+	assert_eq!( posts[0].version, Some(1) );
+
+	let error = get_entity::<CommentEntry,EntryTypes>( &id ).unwrap_err();
+	assert!( matches!( error, UtilsError::DeserializationError(..) ), "{:?}", error );
+
+	let updated = update_entity( &action, edit("new") ).unwrap();
+	assert_eq!( updated.version, Some(2) );
+
+	let fetched : Entity<PostEntry> = get_entity( &id ).unwrap();
+	assert_eq!( fetched.content.message, "new" );
+	assert_eq!( fetched.version, Some(2) );
+
+	let history : Vec<Entity<PostEntry>> = get_entity_history( &id ).unwrap();
+	let versions : Vec<Option<u32>> = history.iter().map( |entity| entity.version ).collect();
+	assert_eq!( versions, vec![ Some(1), Some(2) ] );
+    }
+
+    #[test]
+    fn ambiguous_version_test() {
+	setup();
+
+	let current = create_entity( &comment("current") ).unwrap();
+	let fetched : Entity<CommentEntry> = get_entity( &current.id ).unwrap();
+	assert_eq!( fetched.version, Some(3) );
+
+	let (id, _) = create_raw( &LegacyCommentEntry { body: "old".to_string() }, 1 );
+	let fetched : Entity<CommentEntry> = get_entity( &id ).unwrap();
+	assert_eq!( fetched.content.message, "v1 old" );
+	assert_eq!( fetched.version, Some(1) );
+
+	// Also decodes as version 1 because unknown fields are ignored
+	let (id, _) = create_raw( &EditedCommentEntry { body: "old".to_string(), edited: true }, 1 );
+	let fetched : Entity<CommentEntry> = get_entity( &id ).unwrap();
+	assert_eq!( fetched.content.message, "v2 old" );
+	assert_eq!( fetched.version, None );
+
+	// Decodes as the current shape and as version 1
+	let (id, _) = create_raw( &serde_json::json!({ "message": "both", "body": "both" }), 1 );
+	let fetched : Entity<CommentEntry> = get_entity( &id ).unwrap();
+	assert_eq!( fetched.content.message, "both" );
+	assert_eq!( fetched.version, None );
+    }
+
+    #[test]
+    fn entity_meta_test() {
+	let dht = setup();
+	let alice = dht.agent();
+	let bobby = AgentPubKey::from_raw_32( vec![2; 32] );
+	let created_at = Timestamp::from_micros( 1_000_000 );
+	let updated_at = Timestamp::from_micros( 5_000_000 );
+
+	dht.set_time( created_at );
+	let created = create_entity( &post("original") ).unwrap();
+	let meta = created.meta.unwrap();
+	assert_eq!( meta, EntityMeta {
+	    author: alice.to_owned(),
+	    created_at,
+	    latest_author: alice.to_owned(),
+	    updated_at: created_at,
+	});
+
+	let reply = create_entity( &comment("reply") ).unwrap();
+	reply.link_from( &created.id, LinkTypes::Comment, None ).unwrap();
+
+	dht.set_agent( bobby.to_owned() );
+	dht.set_time( updated_at );
+	let updated = update_entity( &created.action, edit("by bobby") ).unwrap();
+	let expected = EntityMeta {
+	    author: alice.to_owned(),
+	    created_at,
+	    latest_author: bobby,
+	    updated_at,
+	};
+	assert_eq!( updated.meta.as_ref(), Some(&expected) );
+
+	let fetched : Entity<PostEntry> = get_entity( &created.id ).unwrap();
+	assert_eq!( fetched.meta, Some(expected) );
+
+	let comments : Vec<Entity<CommentEntry>> = get_entities( &created.id, LinkTypes::Comment, None ).unwrap();
+	assert_eq!( comments[0].meta.as_ref().unwrap().author, alice );
+    }
+
+    #[test]
+    fn author_only_test() {
+	let dht = setup();
+	let alice = dht.agent();
+	let bobby = AgentPubKey::from_raw_32( vec![2; 32] );
+
+	let created = create_entity( &post("original") ).unwrap();
+	let updated = update_entity_with( &created.action, &UpdateOptions::author_only(), edit("by alice") ).unwrap();
+
+	dht.set_agent( bobby.to_owned() );
+	let error = update_entity_with( &updated.action, &UpdateOptions::author_only(), edit("by bobby") ).unwrap_err();
+	assert!( matches!( error, UtilsError::NotAuthor { ref author, ref caller } if *author == alice && *caller == bobby ), "{:?}", error );
+
+	let error = delete_entity_with::<PostEntry,LinkTypes,EntryTypes>( &created.id, &DeleteOptions::author_only() ).unwrap_err();
+	assert!( matches!( error, UtilsError::NotAuthor { .. } ), "{:?}", error );
+
+	let error = delete_entity_author_only::<PostEntry,EntryTypes>( &created.id ).unwrap_err();
+	assert!( matches!( error, UtilsError::NotAuthor { .. } ), "{:?}", error );
+
+	let by_bobby = update_entity( &updated.action, edit("by bobby") ).unwrap();
+	assert_eq!( by_bobby.id, created.id );
+
+	dht.set_agent( alice );
+	update_entity_with( &by_bobby.action, &UpdateOptions::author_only(), edit("by alice") ).unwrap();
+	delete_entity_author_only::<PostEntry,EntryTypes>( &created.id ).unwrap();
+    }
+
+    #[test]
+    fn get_strategy_test() {
+	let dht = setup();
+	let alice = dht.agent();
+	let bobby = AgentPubKey::from_raw_32( vec![2; 32] );
+	let local = ReadOptions::with_strategy( ReadStrategy::Local );
+
+	let created = create_entity( &post("original") ).unwrap();
+	let reply = create_entity( &comment("reply") ).unwrap();
+	reply.link_from( &created.id, LinkTypes::Comment, None ).unwrap();
+
+	let fetched : Entity<PostEntry> = get_entity_with( &created.id, &local ).unwrap();
+	assert_eq!( fetched.action, created.action );
+
+	dht.set_agent( bobby );
+	let error = get_entity_with::<PostEntry,EntryTypes>( &created.id, &local ).unwrap_err();
+	assert!( matches!( error, UtilsError::EntryNotFoundError(..) ), "{:?}", error );
+
+	let comments : Vec<Entity<CommentEntry>> = get_entities_with( &created.id, LinkTypes::Comment, None, &local ).unwrap();
+	assert!( comments.is_empty() );
+
+	let options = ReadOptions::with_strategy( ReadStrategy::Content );
+	let comments : Vec<Entity<CommentEntry>> = get_entities_with( &created.id, LinkTypes::Comment, None, &options ).unwrap();
+	assert_eq!( comments.len(), 1 );
+
+	let by_bobby = update_entity( &created.action, edit("by bobby") ).unwrap();
+
+	let error = update_entity_with( &created.action, &UpdateOptions { strategy: ReadStrategy::Local, ..Default::default() }, edit("local") ).unwrap_err();
+	assert!( matches!( error, UtilsError::ActionNotFoundError(..) ), "{:?}", error );
+
+	dht.set_agent( alice );
+	let fetched : Entity<PostEntry> = get_entity_with( &created.id, &local ).unwrap();
+	assert_eq!( fetched.action, created.action );
+
+	let fetched : Entity<PostEntry> = get_entity( &created.id ).unwrap();
+	assert_eq!( fetched.action, by_bobby.action );
+
+	let history : Vec<Entity<PostEntry>> = get_entity_history_with( &created.id, &local ).unwrap();
+	assert_eq!( history.len(), 1 );
+
+	update_entity_with( &created.action, &UpdateOptions { strategy: ReadStrategy::Local, allow_branching: true, ..Default::default() }, edit("v2") ).unwrap();
+	let queries = dht.query_count();
+	let history : Vec<Entity<PostEntry>> = get_entity_history_with( &created.id, &local ).unwrap();
+	assert_eq!( history.len(), 2 );
+	assert_eq!( dht.query_count(), queries + 1 );
+
+	let queries = dht.query_count();
+	let page : Page<Entity<CommentEntry>> = get_entities_page_with( &created.id, LinkTypes::Comment, None, 10, None, &local ).unwrap();
+	assert_eq!( page.items.len(), 1 );
+	assert_eq!( dht.query_count(), queries + 1 );
+    }
 }
@@ -0,0 +1,604 @@
+//! An in-memory DHT for unit testing zome functions without a conductor
+//!
+//! [`MockDht`] implements the HDK host interface ([`HdkT`]) over an in-memory store of actions,
+//! entries and links.  Every action is immediately visible to every read, so tests are
+//! deterministic.
+//!
+//! ```ignore
+//! let dht = MockDht::new( UnitEntryTypes::iter().count(), LinkTypes::iter().count() );
+//! dht.install();
+//!
+//! let post = create_entity( &PostEntry { .. } )?;
+//! ```
+//!
+//! Available with the `mock` feature.
+
+use std::collections::HashMap;
+use std::sync::{ Arc, Mutex, MutexGuard };
+use hdk::prelude::*;
+use hdk::hdi::hdi::HdiT;
+use hdk::hdk::HdkT;
+use holo_hash::AnyDhtHashPrimitive;
+
+
+fn unsupported<T>(method: &str) -> ExternResult<T> {
+    Err(wasm_error!(WasmErrorInner::Guest(format!("MockDht does not support '{}'", method ))))
+}
+
+
+struct MockState {
+    agent: AgentPubKey,
+    now: Timestamp,
+    actions: HashMap<ActionHash, SignedActionHashed>,
+    action_order: Vec<ActionHash>,
+    entries: HashMap<EntryHash, Entry>,
+    chain_heads: HashMap<AgentPubKey, (ActionHash, u32)>,
+    query_count: usize,
+}
+
+impl MockState {
+    /// The actions in the order they were committed
+    fn actions(&self) -> impl Iterator<Item = &SignedActionHashed> {
+	self.action_order.iter()
+	    .filter_map( move |hash| self.actions.get( hash ) )
+    }
+
+    fn get_action(&self, hash: &ActionHash) -> ExternResult<SignedActionHashed> {
+	self.actions.get( hash )
+	    .cloned()
+	    .ok_or( wasm_error!(WasmErrorInner::Guest(format!("MockDht has no action '{}'", hash ))) )
+    }
+
+    fn record(&self, sh: &SignedActionHashed) -> Record {
+	let entry = sh.action().entry_hash()
+	    .and_then( |hash| self.entries.get( hash ) )
+	    .cloned();
+
+	Record::new( sh.to_owned(), entry )
+    }
+
+    fn deletes_for(&self, hash: &ActionHash) -> Vec<SignedActionHashed> {
+	self.actions()
+	    .filter( |sh| matches!( sh.action(), Action::Delete(delete) if &delete.deletes_address == hash ) )
+	    .cloned()
+	    .collect()
+    }
+
+    fn updates_for(&self, hash: &ActionHash) -> Vec<SignedActionHashed> {
+	self.actions()
+	    .filter( |sh| matches!( sh.action(), Action::Update(update) if &update.original_action_address == hash ) )
+	    .cloned()
+	    .collect()
+    }
+
+    fn entry_actions(&self, hash: &EntryHash) -> Vec<SignedActionHashed> {
+	self.actions()
+	    .filter( |sh| matches!( sh.action(), Action::Create(_) | Action::Update(_) ) )
+	    .filter( |sh| sh.action().entry_hash() == Some(hash) )
+	    .cloned()
+	    .collect()
+    }
+
+    fn link_deletes_for(&self, hash: &ActionHash) -> Vec<SignedActionHashed> {
+	self.actions()
+	    .filter( |sh| matches!( sh.action(), Action::DeleteLink(delete) if &delete.link_add_address == hash ) )
+	    .cloned()
+	    .collect()
+    }
+
+    fn create_links(&self, base: &AnyLinkableHash, link_type: &LinkTypeFilter, tag_prefix: &Option<LinkTag>) -> Vec<(SignedActionHashed, CreateLink)> {
+	self.actions()
+	    .filter_map( |sh| match sh.action() {
+		Action::CreateLink(create) => Some( (sh.to_owned(), create.to_owned()) ),
+		_ => None,
+	    })
+	    .filter( |(_, create)| &create.base_address == base )
+	    .filter( |(_, create)| match link_type {
+		LinkTypeFilter::Types(types) => types.iter()
+		    .any( |(zome_index, link_types)| {
+			*zome_index == create.zome_index && link_types.contains( &create.link_type )
+		    }),
+		LinkTypeFilter::Dependencies(zomes) => zomes.contains( &create.zome_index ),
+	    })
+	    .filter( |(_, create)| match tag_prefix {
+		Some(prefix) => create.tag.0.starts_with( &prefix.0 ),
+		None => true,
+	    })
+	    .collect()
+    }
+
+    fn links(&self, base: &AnyLinkableHash, link_type: &LinkTypeFilter, tag_prefix: &Option<LinkTag>) -> Vec<Link> {
+	self.create_links( base, link_type, tag_prefix )
+	    .into_iter()
+	    .filter( |(sh, _)| self.link_deletes_for( sh.action_address() ).is_empty() )
+	    .map( |(sh, create)| Link {
+		author: create.author,
+		target: create.target_address,
+		timestamp: create.timestamp,
+		zome_index: create.zome_index,
+		link_type: create.link_type,
+		tag: create.tag,
+		create_link_hash: sh.action_address().to_owned(),
+	    })
+	    .collect()
+    }
+
+    /// Build the next action for the current agent's chain and store it
+    fn commit<F>(&mut self, build: F) -> ExternResult<ActionHash>
+    where
+	F: FnOnce(AgentPubKey, Timestamp, u32, ActionHash) -> Action,
+    {
+	let (prev_action, action_seq) = self.chain_heads.get( &self.agent )
+	    .cloned()
+	    .unwrap_or( (ActionHash::from_raw_32( vec![0; 32] ), 0) );
+	let action = build( self.agent.to_owned(), self.now, action_seq + 1, prev_action );
+	let sh = SignedActionHashed::with_presigned(
+	    ActionHashed::from_content_sync( action ),
+	    Signature([0; 64]),
+	);
+	let hash = sh.action_address().to_owned();
+
+	self.chain_heads.insert( self.agent.to_owned(), (hash.to_owned(), action_seq + 1) );
+	self.actions.insert( hash.to_owned(), sh );
+	self.action_order.push( hash.to_owned() );
+	self.now = Timestamp::from_micros( self.now.as_micros() + 1000 );
+
+	Ok( hash )
+    }
+
+    fn store_entry(&mut self, entry: Entry) -> EntryHash {
+	let hash = EntryHash::with_data_sync( &entry );
+
+	self.entries.insert( hash.to_owned(), entry );
+
+	hash
+    }
+}
+
+
+/// An in-memory implementation of the HDK host functions
+///
+/// Clones share the same store, so a test can keep a handle after calling [`MockDht::install`]
+/// (eg. to switch agents or control the clock).
+#[derive(Clone)]
+pub struct MockDht {
+    zome_types: ScopedZomeTypesSet,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockDht {
+    /// Create an empty DHT for a single zome (zome index 0) with the given number of entry and link
+    /// types
+    pub fn new(entry_type_count: usize, link_type_count: usize) -> Self {
+	MockDht {
+	    zome_types: ScopedZomeTypesSet {
+		entries: ScopedZomeTypes(vec![
+		    ( 0.into(), (0..entry_type_count).map( |i| EntryDefIndex( i as u8 ) ).collect() ),
+		]),
+		links: ScopedZomeTypes(vec![
+		    ( 0.into(), (0..link_type_count).map( |i| LinkType( i as u8 ) ).collect() ),
+		]),
+	    },
+	    state: Arc::new( Mutex::new( MockState {
+		agent: AgentPubKey::from_raw_32( vec![1; 32] ),
+		now: Timestamp::from_micros( 1_600_000_000_000_000 ),
+		actions: HashMap::new(),
+		action_order: Vec::new(),
+		entries: HashMap::new(),
+		chain_heads: HashMap::new(),
+		query_count: 0,
+	    })),
+	}
+    }
+
+    /// Register this DHT as the HDK for the current thread
+    pub fn install(&self) {
+	set_hdk( self.to_owned() );
+    }
+
+    fn state(&self) -> MutexGuard<'_, MockState> {
+	self.state.lock()
+	    .expect("MockDht state lock is poisoned")
+    }
+
+    /// The agent that authors new actions
+    pub fn agent(&self) -> AgentPubKey {
+	self.state().agent.to_owned()
+    }
+
+    /// Change the agent that authors new actions
+    pub fn set_agent(&self, agent: AgentPubKey) {
+	self.state().agent = agent;
+    }
+
+    /// The timestamp used for the next action
+    ///
+    /// The clock advances by 1ms after every action.
+    pub fn now(&self) -> Timestamp {
+	self.state().now
+    }
+
+    /// Set the timestamp used for the next action
+    pub fn set_time(&self, timestamp: Timestamp) {
+	self.state().now = timestamp;
+    }
+
+    /// The number of actions committed so far
+    pub fn action_count(&self) -> usize {
+	self.state().action_order.len()
+    }
+
+    /// The number of source chain queries made so far
+    pub fn query_count(&self) -> usize {
+	self.state().query_count
+    }
+}
+
+
+impl HdiT for MockDht {
+    fn verify_signature(&self, _: VerifySignature) -> ExternResult<bool> {
+	Ok( true )
+    }
+
+    fn hash(&self, hash_input: HashInput) -> ExternResult<HashOutput> {
+	Ok( match hash_input {
+	    HashInput::Entry(entry) => HashOutput::Entry( EntryHash::with_data_sync( &entry ) ),
+	    HashInput::Action(action) => HashOutput::Action( ActionHash::with_data_sync( &action ) ),
+	    _ => unsupported("hash")?,
+	})
+    }
+
+    fn must_get_entry(&self, input: MustGetEntryInput) -> ExternResult<EntryHashed> {
+	let hash = input.into_inner();
+
+	self.state().entries.get( &hash )
+	    .cloned()
+	    .map( |entry| EntryHashed::with_pre_hashed( entry, hash.to_owned() ) )
+	    .ok_or( wasm_error!(WasmErrorInner::Guest(format!("MockDht has no entry '{}'", hash ))) )
+    }
+
+    fn must_get_action(&self, input: MustGetActionInput) -> ExternResult<SignedActionHashed> {
+	self.state().get_action( &input.into_inner() )
+    }
+
+    fn must_get_valid_record(&self, input: MustGetValidRecordInput) -> ExternResult<Record> {
+	let state = self.state();
+	let sh = state.get_action( &input.into_inner() )?;
+
+	Ok( state.record( &sh ) )
+    }
+
+    fn must_get_agent_activity(&self, _: MustGetAgentActivityInput) -> ExternResult<Vec<RegisterAgentActivity>> {
+	unsupported("must_get_agent_activity")
+    }
+
+    fn dna_info(&self, _: ()) -> ExternResult<DnaInfo> {
+	unsupported("dna_info")
+    }
+
+    fn zome_info(&self, _: ()) -> ExternResult<ZomeInfo> {
+	Ok( ZomeInfo::new(
+	    "mock".into(),
+	    0.into(),
+	    SerializedBytes::default(),
+	    Vec::new().into(),
+	    Vec::new(),
+	    self.zome_types.to_owned(),
+	))
+    }
+
+    fn trace(&self, _: TraceMsg) -> ExternResult<()> {
+	Ok(())
+    }
+
+    fn x_salsa20_poly1305_decrypt(&self, _: XSalsa20Poly1305Decrypt) -> ExternResult<Option<XSalsa20Poly1305Data>> {
+	unsupported("x_salsa20_poly1305_decrypt")
+    }
+
+    fn x_25519_x_salsa20_poly1305_decrypt(&self, _: X25519XSalsa20Poly1305Decrypt) -> ExternResult<Option<XSalsa20Poly1305Data>> {
+	unsupported("x_25519_x_salsa20_poly1305_decrypt")
+    }
+}
+
+
+impl HdkT for MockDht {
+    fn get_agent_activity(&self, _: GetAgentActivityInput) -> ExternResult<AgentActivity> {
+	unsupported("get_agent_activity")
+    }
+
+    fn query(&self, filter: ChainQueryFilter) -> ExternResult<Vec<Record>> {
+	let mut state = self.state();
+	state.query_count += 1;
+	let records = state.actions()
+	    .filter( |sh| sh.action().author() == &state.agent )
+	    .map( |sh| match filter.include_entries {
+		true => state.record( sh ),
+		false => Record::new( sh.to_owned(), None ),
+	    })
+	    .collect();
+	let mut records = filter.filter_records( records );
+
+	if filter.order_descending {
+	    records.reverse();
+	}
+
+	Ok( records )
+    }
+
+    fn sign(&self, _: Sign) -> ExternResult<Signature> {
+	unsupported("sign")
+    }
+
+    fn sign_ephemeral(&self, _: SignEphemeral) -> ExternResult<EphemeralSignatures> {
+	unsupported("sign_ephemeral")
+    }
+
+    fn create(&self, input: CreateInput) -> ExternResult<ActionHash> {
+	let mut state = self.state();
+	let entry_type = match input.entry_location {
+	    EntryDefLocation::App(location) => EntryType::App(AppEntryDef::new(
+		location.entry_def_index,
+		location.zome_index,
+		input.entry_visibility,
+	    )),
+	    EntryDefLocation::CapClaim => EntryType::CapClaim,
+	    EntryDefLocation::CapGrant => EntryType::CapGrant,
+	};
+	let entry_hash = state.store_entry( input.entry );
+
+	state.commit( |author, timestamp, action_seq, prev_action| Action::Create(Create {
+	    author,
+	    timestamp,
+	    action_seq,
+	    prev_action,
+	    entry_type,
+	    entry_hash,
+	    weight: Default::default(),
+	}))
+    }
+
+    fn update(&self, input: UpdateInput) -> ExternResult<ActionHash> {
+	let UpdateInput { original_action_address, entry, .. } = input;
+	let mut state = self.state();
+	let original = state.get_action( &original_action_address )?;
+	let (original_entry_address, entry_type) = match ( original.action().entry_hash(), original.action().entry_type() ) {
+	    (Some(hash), Some(entry_type)) => (hash.to_owned(), entry_type.to_owned()),
+	    _ => Err(wasm_error!(WasmErrorInner::Guest(format!("MockDht cannot update action '{}' because it has no entry", original_action_address ))))?,
+	};
+	let entry_hash = state.store_entry( entry );
+
+	state.commit( |author, timestamp, action_seq, prev_action| Action::Update(Update {
+	    author,
+	    timestamp,
+	    action_seq,
+	    prev_action,
+	    original_action_address,
+	    original_entry_address,
+	    entry_type,
+	    entry_hash,
+	    weight: Default::default(),
+	}))
+    }
+
+    fn delete(&self, input: DeleteInput) -> ExternResult<ActionHash> {
+	let mut state = self.state();
+	let original = state.get_action( &input.deletes_action_hash )?;
+	let deletes_entry_address = original.action().entry_hash()
+	    .cloned()
+	    .ok_or( wasm_error!(WasmErrorInner::Guest(format!("MockDht cannot delete action '{}' because it has no entry", input.deletes_action_hash ))) )?;
+
+	state.commit( |author, timestamp, action_seq, prev_action| Action::Delete(Delete {
+	    author,
+	    timestamp,
+	    action_seq,
+	    prev_action,
+	    deletes_address: input.deletes_action_hash,
+	    deletes_entry_address,
+	    weight: Default::default(),
+	}))
+    }
+
+    fn get(&self, inputs: Vec<GetInput>) -> ExternResult<Vec<Option<Record>>> {
+	let state = self.state();
+
+	Ok( inputs.into_iter()
+	    .map( |input| match input.any_dht_hash.into_primitive() {
+		AnyDhtHashPrimitive::Action(hash) => state.actions.get( &hash )
+		    .map( |sh| state.record( sh ) ),
+		AnyDhtHashPrimitive::Entry(hash) => state.entry_actions( &hash )
+		    .into_iter()
+		    .find( |sh| state.deletes_for( sh.action_address() ).is_empty() )
+		    .map( |sh| state.record( &sh ) ),
+	    })
+	    .collect() )
+    }
+
+    fn get_details(&self, inputs: Vec<GetInput>) -> ExternResult<Vec<Option<Details>>> {
+	let state = self.state();
+
+	Ok( inputs.into_iter()
+	    .map( |input| match input.any_dht_hash.into_primitive() {
+		AnyDhtHashPrimitive::Action(hash) => state.actions.get( &hash )
+		    .map( |sh| Details::Record(RecordDetails {
+			record: state.record( sh ),
+			validation_status: ValidationStatus::Valid,
+			deletes: state.deletes_for( &hash ),
+			updates: state.updates_for( &hash ),
+		    })),
+		AnyDhtHashPrimitive::Entry(hash) => state.entries.get( &hash )
+		    .map( |entry| {
+			let actions = state.entry_actions( &hash );
+			let is_live = actions.iter()
+			    .any( |sh| state.deletes_for( sh.action_address() ).is_empty() );
+
+			Details::Entry(EntryDetails {
+			    entry: entry.to_owned(),
+			    deletes: actions.iter()
+				.flat_map( |sh| state.deletes_for( sh.action_address() ) )
+				.collect(),
+			    updates: actions.iter()
+				.flat_map( |sh| state.updates_for( sh.action_address() ) )
+				.collect(),
+			    actions,
+			    rejected_actions: Vec::new(),
+			    entry_dht_status: match is_live {
+				true => EntryDhtStatus::Live,
+				false => EntryDhtStatus::Dead,
+			    },
+			})
+		    }),
+	    })
+	    .collect() )
+    }
+
+    fn accept_countersigning_preflight_request(&self, _: PreflightRequest) -> ExternResult<PreflightRequestAcceptance> {
+	unsupported("accept_countersigning_preflight_request")
+    }
+
+    fn agent_info(&self, _: ()) -> ExternResult<AgentInfo> {
+	let state = self.state();
+	let (head, seq) = state.chain_heads.get( &state.agent )
+	    .cloned()
+	    .unwrap_or( (ActionHash::from_raw_32( vec![0; 32] ), 0) );
+
+	Ok(AgentInfo {
+	    agent_initial_pubkey: state.agent.to_owned(),
+	    agent_latest_pubkey: state.agent.to_owned(),
+	    chain_head: (head, seq, state.now),
+	})
+    }
+
+    fn call_info(&self, _: ()) -> ExternResult<CallInfo> {
+	unsupported("call_info")
+    }
+
+    fn create_link(&self, input: CreateLinkInput) -> ExternResult<ActionHash> {
+	self.state().commit( |author, timestamp, action_seq, prev_action| Action::CreateLink(CreateLink {
+	    author,
+	    timestamp,
+	    action_seq,
+	    prev_action,
+	    base_address: input.base_address,
+	    target_address: input.target_address,
+	    zome_index: input.zome_index,
+	    link_type: input.link_type,
+	    tag: input.tag,
+	    weight: Default::default(),
+	}))
+    }
+
+    fn delete_link(&self, input: DeleteLinkInput) -> ExternResult<ActionHash> {
+	let mut state = self.state();
+	let base_address = match state.get_action( &input.address )?.action() {
+	    Action::CreateLink(create) => create.base_address.to_owned(),
+	    _ => Err(wasm_error!(WasmErrorInner::Guest(format!("MockDht action '{}' is not a CreateLink", input.address ))))?,
+	};
+
+	state.commit( |author, timestamp, action_seq, prev_action| Action::DeleteLink(DeleteLink {
+	    author,
+	    timestamp,
+	    action_seq,
+	    prev_action,
+	    base_address,
+	    link_add_address: input.address,
+	}))
+    }
+
+    fn get_links(&self, inputs: Vec<GetLinksInput>) -> ExternResult<Vec<Vec<Link>>> {
+	let state = self.state();
+
+	Ok( inputs.into_iter()
+	    .map( |input| state.links( &input.base_address, &input.link_type, &input.tag_prefix ) )
+	    .collect() )
+    }
+
+    fn get_link_details(&self, inputs: Vec<GetLinksInput>) -> ExternResult<Vec<LinkDetails>> {
+	let state = self.state();
+
+	Ok( inputs.into_iter()
+	    .map( |input| {
+		state.create_links( &input.base_address, &input.link_type, &input.tag_prefix )
+		    .into_iter()
+		    .map( |(sh, _)| {
+			let deletes = state.link_deletes_for( sh.action_address() );
+			(sh, deletes)
+		    })
+		    .collect::<Vec<_>>()
+		    .into()
+	    })
+	    .collect() )
+    }
+
+    // `Option::is_none_or` is newer than the Holonix toolchain
+    #[allow(clippy::unnecessary_map_or)]
+    fn count_links(&self, query: LinkQuery) -> ExternResult<usize> {
+	let state = self.state();
+
+	Ok( state.links( &query.base, &query.link_type, &query.tag_prefix )
+	    .into_iter()
+	    .filter( |link| query.after.map_or( true, |after| link.timestamp > after ) )
+	    .filter( |link| query.before.map_or( true, |before| link.timestamp < before ) )
+	    .filter( |link| query.author.as_ref().map_or( true, |author| &link.author == author ) )
+	    .count() )
+    }
+
+    fn block_agent(&self, _: BlockAgentInput) -> ExternResult<()> {
+	unsupported("block_agent")
+    }
+
+    fn unblock_agent(&self, _: BlockAgentInput) -> ExternResult<()> {
+	unsupported("unblock_agent")
+    }
+
+    fn call(&self, _: Vec<Call>) -> ExternResult<Vec<ZomeCallResponse>> {
+	unsupported("call")
+    }
+
+    fn emit_signal(&self, _: AppSignal) -> ExternResult<()> {
+	unsupported("emit_signal")
+    }
+
+    fn remote_signal(&self, _: RemoteSignal) -> ExternResult<()> {
+	unsupported("remote_signal")
+    }
+
+    fn random_bytes(&self, _: u32) -> ExternResult<Bytes> {
+	unsupported("random_bytes")
+    }
+
+    fn sys_time(&self, _: ()) -> ExternResult<Timestamp> {
+	Ok( self.state().now )
+    }
+
+    fn schedule(&self, _: String) -> ExternResult<()> {
+	unsupported("schedule")
+    }
+
+    fn sleep(&self, _: std::time::Duration) -> ExternResult<()> {
+	unsupported("sleep")
+    }
+
+    fn x_salsa20_poly1305_shared_secret_create_random(&self, _: Option<XSalsa20Poly1305KeyRef>) -> ExternResult<XSalsa20Poly1305KeyRef> {
+	unsupported("x_salsa20_poly1305_shared_secret_create_random")
+    }
+
+    fn x_salsa20_poly1305_shared_secret_export(&self, _: XSalsa20Poly1305SharedSecretExport) -> ExternResult<XSalsa20Poly1305EncryptedData> {
+	unsupported("x_salsa20_poly1305_shared_secret_export")
+    }
+
+    fn x_salsa20_poly1305_shared_secret_ingest(&self, _: XSalsa20Poly1305SharedSecretIngest) -> ExternResult<XSalsa20Poly1305KeyRef> {
+	unsupported("x_salsa20_poly1305_shared_secret_ingest")
+    }
+
+    fn x_salsa20_poly1305_encrypt(&self, _: XSalsa20Poly1305Encrypt) -> ExternResult<XSalsa20Poly1305EncryptedData> {
+	unsupported("x_salsa20_poly1305_encrypt")
+    }
+
+    fn create_x25519_keypair(&self, _: ()) -> ExternResult<X25519PubKey> {
+	unsupported("create_x25519_keypair")
+    }
+
+    fn x_25519_x_salsa20_poly1305_encrypt(&self, _: X25519XSalsa20Poly1305Encrypt) -> ExternResult<XSalsa20Poly1305EncryptedData> {
+	unsupported("x_25519_x_salsa20_poly1305_encrypt")
+    }
+}
@@ -0,0 +1,225 @@
+use hdk::prelude::*;
+use crate::branches::{ BranchResolver, EarliestUpdate };
+
+
+/// The default limit for the number of actions visited when walking an update chain
+pub const DEFAULT_MAX_DEPTH : usize = 1000;
+
+
+/// Where reads look for records
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ReadStrategy {
+    /// Only the agent's own source chain; records authored by other agents are not found
+    Local,
+
+    /// Ask the network for the latest metadata (updates, deletes)
+    #[default]
+    Latest,
+
+    /// Use local content when it is available and only go to the network when it is missing
+    Content,
+}
+
+
+/// Options for controlling the behaviour of [`update_entity_with`](crate::update_entity_with)
+#[derive(Clone, Serialize, Deserialize)]
+pub struct UpdateOptions<'a> {
+    /// Allow the update to branch from an action that is not the latest in the update chain
+    pub allow_branching: bool,
+
+    /// Where the current version and its update chain are read from
+    #[serde(default)]
+    pub strategy: ReadStrategy,
+
+    /// Fail with [`UtilsError::NotAuthor`](crate::UtilsError::NotAuthor) unless the caller
+    /// authored the entity's Create action
+    #[serde(default)]
+    pub author_only: bool,
+
+    /// Chooses which update is the latest version when the update chain has branched
+    #[serde(skip, default = "default_resolver")]
+    pub resolver: &'a dyn BranchResolver,
+}
+
+impl<'a> UpdateOptions<'a> {
+    /// Options that intentionally branch from an older version
+    pub fn branching() -> Self {
+	UpdateOptions {
+	    allow_branching: true,
+	    ..Default::default()
+	}
+    }
+
+    /// Options that only allow the entity's original author to update it
+    pub fn author_only() -> Self {
+	UpdateOptions {
+	    author_only: true,
+	    ..Default::default()
+	}
+    }
+
+    /// Options that update the latest version chosen by the given branch resolver
+    pub fn with_resolver(resolver: &'a dyn BranchResolver) -> Self {
+	UpdateOptions {
+	    resolver,
+	    ..Default::default()
+	}
+    }
+
+    /// The [`ReadOptions`] used to find the current version
+    pub(crate) fn read_options(&self) -> ReadOptions<'a> {
+	ReadOptions {
+	    resolver: self.resolver,
+	    strategy: self.strategy,
+	    ..Default::default()
+	}
+    }
+}
+
+impl Default for UpdateOptions<'_> {
+    fn default() -> Self {
+	UpdateOptions {
+	    allow_branching: false,
+	    strategy: ReadStrategy::default(),
+	    author_only: false,
+	    resolver: &EarliestUpdate,
+	}
+    }
+}
+
+impl std::fmt::Debug for UpdateOptions<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+	f.debug_struct("UpdateOptions")
+	    .field("allow_branching", &self.allow_branching )
+	    .field("strategy", &self.strategy )
+	    .field("author_only", &self.author_only )
+	    .finish_non_exhaustive()
+    }
+}
+
+fn default_resolver() -> &'static dyn BranchResolver {
+    &EarliestUpdate
+}
+
+
+/// Options for controlling which links are deleted by [`Entity::unlink_from_with`](crate::Entity::unlink_from_with)
+/// and [`Entity::unlink_to_with`](crate::Entity::unlink_to_with)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UnlinkOptions {
+    /// Only delete the earliest matching link instead of every matching link
+    pub first_only: bool,
+}
+
+impl UnlinkOptions {
+    /// Options that only delete the earliest matching link
+    pub fn first_only() -> Self {
+	UnlinkOptions {
+	    first_only: true,
+	}
+    }
+}
+
+
+/// Options for controlling which links are deleted along with an entity by
+/// [`delete_entity_with`](crate::delete_entity_with)
+#[derive(Debug, Clone)]
+pub struct DeleteOptions<LT> {
+    /// Delete every link of these types where the entity is the base
+    pub outgoing: Vec<LT>,
+
+    /// Delete every link of the given type from the given base to the entity
+    pub incoming: Vec<(EntryHash, LT)>,
+
+    /// Fail with [`UtilsError::NotAuthor`](crate::UtilsError::NotAuthor) unless the caller
+    /// authored the entity's Create action
+    pub author_only: bool,
+}
+
+impl<LT> DeleteOptions<LT> {
+    /// Options that only allow the entity's original author to delete it
+    pub fn author_only() -> Self {
+	DeleteOptions {
+	    author_only: true,
+	    ..Default::default()
+	}
+    }
+}
+
+impl<LT> Default for DeleteOptions<LT> {
+    fn default() -> Self {
+	DeleteOptions {
+	    outgoing: Vec::new(),
+	    incoming: Vec::new(),
+	    author_only: false,
+	}
+    }
+}
+
+
+/// Options for choosing which rules [`validate_ceps_op_with`](crate::validate_ceps_op_with)
+/// enforces
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ValidationOptions {
+    /// Only the author of an entity's Create action may update or delete it
+    pub author_only: bool,
+}
+
+impl ValidationOptions {
+    /// Options that reject updates and deletes not made by the entity's original author
+    pub fn author_only() -> Self {
+	ValidationOptions {
+	    author_only: true,
+	}
+    }
+}
+
+
+/// Options for controlling how the update chain is read (eg. [`get_entity_with`](crate::get_entity_with))
+#[derive(Clone, Copy)]
+pub struct ReadOptions<'a> {
+    /// Chooses which update to follow when the update chain has branched
+    pub resolver: &'a dyn BranchResolver,
+
+    /// The maximum number of actions to visit when walking an update chain
+    pub max_depth: usize,
+
+    /// Where records are read from
+    pub strategy: ReadStrategy,
+}
+
+impl<'a> ReadOptions<'a> {
+    /// Options that use the given branch resolver
+    pub fn with_resolver(resolver: &'a dyn BranchResolver) -> Self {
+	ReadOptions {
+	    resolver,
+	    ..Default::default()
+	}
+    }
+
+    /// Options that read using the given [`ReadStrategy`]
+    pub fn with_strategy(strategy: ReadStrategy) -> Self {
+	ReadOptions {
+	    strategy,
+	    ..Default::default()
+	}
+    }
+}
+
+impl Default for ReadOptions<'_> {
+    fn default() -> Self {
+	ReadOptions {
+	    resolver: &EarliestUpdate,
+	    max_depth: DEFAULT_MAX_DEPTH,
+	    strategy: ReadStrategy::default(),
+	}
+    }
+}
+
+impl std::fmt::Debug for ReadOptions<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+	f.debug_struct("ReadOptions")
+	    .field("max_depth", &self.max_depth )
+	    .field("strategy", &self.strategy )
+	    .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,738 @@
+//! An order-preserving serde encoding for link tags
+//!
+//! The encoded bytes sort in the same order as the values they were encoded from, and the encoding
+//! of a struct (or tuple) is a byte prefix of the encoding of any struct that starts with the same
+//! fields.  This makes it safe to filter links by a partial tag, eg. `("category",)` matches every
+//! tag of type `(String, u64)` whose first field is `"category"`.
+//!
+//! - Integers are big-endian (signed integers have the sign bit flipped)
+//! - Strings and bytes are escaped (`0x00` becomes `0x00 0xFF`) and terminated by `0x00 0x01`
+//! - Options, sequences and maps use a marker byte before each item
+//! - Structs and tuples are the concatenation of their fields
+//! - Enum variants are prefixed with their big-endian variant index
+//!
+//! The format is not self-describing so the decoding type must match the encoding type.
+
+use std::fmt;
+use hdk::prelude::*;
+use serde::{ de, ser };
+use serde::de::{ DeserializeOwned, IntoDeserializer };
+use crate::errors::{ UtilsResult, UtilsError };
+
+
+/// Encode a value as a link tag
+pub fn encode_tag<T>(value: &T) -> UtilsResult<LinkTag>
+where
+    T: Serialize + ?Sized,
+{
+    let mut serializer = TagSerializer { output: Vec::new() };
+    value.serialize( &mut serializer )?;
+
+    Ok( LinkTag::new( serializer.output ) )
+}
+
+/// Decode a link tag that was created with [`encode_tag`]
+pub fn decode_tag<T>(tag: &LinkTag) -> UtilsResult<T>
+where
+    T: DeserializeOwned,
+{
+    let mut deserializer = TagDeserializer { input: &tag.0 };
+    let value = T::deserialize( &mut deserializer )?;
+
+    if !deserializer.input.is_empty() {
+	return Err(UtilsError::LinkTagError(format!("{} unexpected trailing bytes", deserializer.input.len() )));
+    }
+
+    Ok( value )
+}
+
+
+const STRING_TERMINATOR : [u8; 2] = [ 0x00, 0x01 ];
+const ESCAPED_NULL : [u8; 2] = [ 0x00, 0xFF ];
+const ITEM_MARKER : u8 = 0x01;
+const END_MARKER : u8 = 0x00;
+
+
+#[derive(Debug)]
+struct Error(String);
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	write!( f, "{}", self.0 )
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+	Error( msg.to_string() )
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+	Error( msg.to_string() )
+    }
+}
+
+impl From<Error> for UtilsError {
+    fn from(error: Error) -> Self {
+	UtilsError::LinkTagError( error.0 )
+    }
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+
+
+struct TagSerializer {
+    output: Vec<u8>,
+}
+
+impl TagSerializer {
+    fn write_escaped(&mut self, bytes: &[u8]) {
+	for byte in bytes {
+	    match *byte {
+		0x00 => self.output.extend_from_slice( &ESCAPED_NULL ),
+		byte => self.output.push( byte ),
+	    }
+	}
+	self.output.extend_from_slice( &STRING_TERMINATOR );
+    }
+
+    fn write_variant(&mut self, index: u32) {
+	self.output.extend_from_slice( &index.to_be_bytes() );
+    }
+}
+
+impl ser::Serializer for &mut TagSerializer {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn serialize_bool(self, v: bool) -> Result<()> {
+	self.output.push( v as u8 );
+	Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<()> {
+	self.serialize_u8( (v as u8) ^ (1 << 7) )
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<()> {
+	self.serialize_u16( (v as u16) ^ (1 << 15) )
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<()> {
+	self.serialize_u32( (v as u32) ^ (1 << 31) )
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<()> {
+	self.serialize_u64( (v as u64) ^ (1 << 63) )
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<()> {
+	self.serialize_u128( (v as u128) ^ (1 << 127) )
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<()> {
+	self.output.push( v );
+	Ok(())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<()> {
+	self.output.extend_from_slice( &v.to_be_bytes() );
+	Ok(())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<()> {
+	self.output.extend_from_slice( &v.to_be_bytes() );
+	Ok(())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<()> {
+	self.output.extend_from_slice( &v.to_be_bytes() );
+	Ok(())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<()> {
+	self.output.extend_from_slice( &v.to_be_bytes() );
+	Ok(())
+    }
+
+    // Negative floats have every bit flipped so that larger magnitudes sort first
+    fn serialize_f32(self, v: f32) -> Result<()> {
+	let bits = v.to_bits();
+	self.serialize_u32( if bits >> 31 == 1 { !bits } else { bits ^ (1 << 31) } )
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<()> {
+	let bits = v.to_bits();
+	self.serialize_u64( if bits >> 63 == 1 { !bits } else { bits ^ (1 << 63) } )
+    }
+
+    fn serialize_char(self, v: char) -> Result<()> {
+	self.serialize_u32( v as u32 )
+    }
+
+    fn serialize_str(self, v: &str) -> Result<()> {
+	self.write_escaped( v.as_bytes() );
+	Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+	self.write_escaped( v );
+	Ok(())
+    }
+
+    fn serialize_none(self) -> Result<()> {
+	self.output.push( END_MARKER );
+	Ok(())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<()> {
+	self.output.push( ITEM_MARKER );
+	value.serialize( self )
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+	Ok(())
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<()> {
+	Ok(())
+    }
+
+    fn serialize_unit_variant(self, _: &'static str, index: u32, _: &'static str) -> Result<()> {
+	self.write_variant( index );
+	Ok(())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _: &'static str, value: &T) -> Result<()> {
+	value.serialize( self )
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(self, _: &'static str, index: u32, _: &'static str, value: &T) -> Result<()> {
+	self.write_variant( index );
+	value.serialize( self )
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self> {
+	Ok( self )
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self> {
+	Ok( self )
+    }
+
+    fn serialize_tuple_struct(self, _: &'static str, _: usize) -> Result<Self> {
+	Ok( self )
+    }
+
+    fn serialize_tuple_variant(self, _: &'static str, index: u32, _: &'static str, _: usize) -> Result<Self> {
+	self.write_variant( index );
+	Ok( self )
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self> {
+	Ok( self )
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self> {
+	Ok( self )
+    }
+
+    fn serialize_struct_variant(self, _: &'static str, index: u32, _: &'static str, _: usize) -> Result<Self> {
+	self.write_variant( index );
+	Ok( self )
+    }
+}
+
+impl ser::SerializeSeq for &mut TagSerializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+	self.output.push( ITEM_MARKER );
+	value.serialize( &mut **self )
+    }
+
+    fn end(self) -> Result<()> {
+	self.output.push( END_MARKER );
+	Ok(())
+    }
+}
+
+impl ser::SerializeMap for &mut TagSerializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<()> {
+	self.output.push( ITEM_MARKER );
+	key.serialize( &mut **self )
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+	value.serialize( &mut **self )
+    }
+
+    fn end(self) -> Result<()> {
+	self.output.push( END_MARKER );
+	Ok(())
+    }
+}
+
+impl ser::SerializeTuple for &mut TagSerializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+	value.serialize( &mut **self )
+    }
+
+    fn end(self) -> Result<()> {
+	Ok(())
+    }
+}
+
+impl ser::SerializeTupleStruct for &mut TagSerializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+	value.serialize( &mut **self )
+    }
+
+    fn end(self) -> Result<()> {
+	Ok(())
+    }
+}
+
+impl ser::SerializeTupleVariant for &mut TagSerializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+	value.serialize( &mut **self )
+    }
+
+    fn end(self) -> Result<()> {
+	Ok(())
+    }
+}
+
+impl ser::SerializeStruct for &mut TagSerializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, _: &'static str, value: &T) -> Result<()> {
+	value.serialize( &mut **self )
+    }
+
+    fn end(self) -> Result<()> {
+	Ok(())
+    }
+}
+
+impl ser::SerializeStructVariant for &mut TagSerializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, _: &'static str, value: &T) -> Result<()> {
+	value.serialize( &mut **self )
+    }
+
+    fn end(self) -> Result<()> {
+	Ok(())
+    }
+}
+
+
+
+struct TagDeserializer<'de> {
+    input: &'de [u8],
+}
+
+impl<'de> TagDeserializer<'de> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N]> {
+	if self.input.len() < N {
+	    return Err(Error( "unexpected end of tag".to_string() ));
+	}
+	let (bytes, rest) = self.input.split_at( N );
+	self.input = rest;
+
+	let mut array = [0; N];
+	array.copy_from_slice( bytes );
+	Ok( array )
+    }
+
+    fn read_marker(&mut self) -> Result<bool> {
+	match self.take::<1>()?[0] {
+	    ITEM_MARKER => Ok( true ),
+	    END_MARKER => Ok( false ),
+	    other => Err(Error( format!("invalid marker byte {:#04x}", other ) )),
+	}
+    }
+
+    fn read_escaped(&mut self) -> Result<Vec<u8>> {
+	let mut bytes = Vec::new();
+
+	loop {
+	    match self.take::<1>()?[0] {
+		0x00 => match self.take::<1>()?[0] {
+		    0x01 => return Ok( bytes ),
+		    0xFF => bytes.push( 0x00 ),
+		    other => return Err(Error( format!("invalid escape byte {:#04x}", other ) )),
+		},
+		byte => bytes.push( byte ),
+	    }
+	}
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+	Ok( u32::from_be_bytes( self.take()? ) )
+    }
+
+    fn read_u64(&mut self) -> Result<u64> {
+	Ok( u64::from_be_bytes( self.take()? ) )
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut TagDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, _: V) -> Result<V::Value> {
+	Err(Error( "link tag encoding is not self-describing".to_string() ))
+    }
+
+    fn deserialize_bool<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+	match self.take::<1>()?[0] {
+	    0 => visitor.visit_bool( false ),
+	    1 => visitor.visit_bool( true ),
+	    other => Err(Error( format!("invalid bool byte {:#04x}", other ) )),
+	}
+    }
+
+    fn deserialize_i8<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+	visitor.visit_i8( (u8::from_be_bytes( self.take()? ) ^ (1 << 7)) as i8 )
+    }
+
+    fn deserialize_i16<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+	visitor.visit_i16( (u16::from_be_bytes( self.take()? ) ^ (1 << 15)) as i16 )
+    }
+
+    fn deserialize_i32<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+	visitor.visit_i32( (self.read_u32()? ^ (1 << 31)) as i32 )
+    }
+
+    fn deserialize_i64<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+	visitor.visit_i64( (self.read_u64()? ^ (1 << 63)) as i64 )
+    }
+
+    fn deserialize_i128<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+	visitor.visit_i128( (u128::from_be_bytes( self.take()? ) ^ (1 << 127)) as i128 )
+    }
+
+    fn deserialize_u8<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+	visitor.visit_u8( u8::from_be_bytes( self.take()? ) )
+    }
+
+    fn deserialize_u16<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+	visitor.visit_u16( u16::from_be_bytes( self.take()? ) )
+    }
+
+    fn deserialize_u32<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+	visitor.visit_u32( self.read_u32()? )
+    }
+
+    fn deserialize_u64<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+	visitor.visit_u64( self.read_u64()? )
+    }
+
+    fn deserialize_u128<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+	visitor.visit_u128( u128::from_be_bytes( self.take()? ) )
+    }
+
+    fn deserialize_f32<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+	let bits = self.read_u32()?;
+	visitor.visit_f32( f32::from_bits( if bits >> 31 == 1 { bits ^ (1 << 31) } else { !bits } ) )
+    }
+
+    fn deserialize_f64<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+	let bits = self.read_u64()?;
+	visitor.visit_f64( f64::from_bits( if bits >> 63 == 1 { bits ^ (1 << 63) } else { !bits } ) )
+    }
+
+    fn deserialize_char<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+	let value = self.read_u32()?;
+	visitor.visit_char(
+	    char::from_u32( value )
+		.ok_or_else( || Error( format!("invalid char {:#x}", value ) ) )?
+	)
+    }
+
+    fn deserialize_str<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+	self.deserialize_string( visitor )
+    }
+
+    fn deserialize_string<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+	let bytes = self.read_escaped()?;
+	visitor.visit_string(
+	    String::from_utf8( bytes )
+		.map_err( |error| Error( error.to_string() ) )?
+	)
+    }
+
+    fn deserialize_bytes<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+	self.deserialize_byte_buf( visitor )
+    }
+
+    fn deserialize_byte_buf<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+	visitor.visit_byte_buf( self.read_escaped()? )
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+	match self.read_marker()? {
+	    true => visitor.visit_some( self ),
+	    false => visitor.visit_none(),
+	}
+    }
+
+    fn deserialize_unit<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+	visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: de::Visitor<'de>>(self, _: &'static str, visitor: V) -> Result<V::Value> {
+	visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(self, _: &'static str, visitor: V) -> Result<V::Value> {
+	visitor.visit_newtype_struct( self )
+    }
+
+    fn deserialize_seq<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+	visitor.visit_seq( MarkedAccess { de: self } )
+    }
+
+    fn deserialize_tuple<V: de::Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
+	visitor.visit_seq( FixedAccess { de: self, remaining: len } )
+    }
+
+    fn deserialize_tuple_struct<V: de::Visitor<'de>>(self, _: &'static str, len: usize, visitor: V) -> Result<V::Value> {
+	self.deserialize_tuple( len, visitor )
+    }
+
+    fn deserialize_map<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+	visitor.visit_map( MarkedAccess { de: self } )
+    }
+
+    fn deserialize_struct<V: de::Visitor<'de>>(self, _: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value> {
+	self.deserialize_tuple( fields.len(), visitor )
+    }
+
+    fn deserialize_enum<V: de::Visitor<'de>>(self, _: &'static str, _: &'static [&'static str], visitor: V) -> Result<V::Value> {
+	visitor.visit_enum( self )
+    }
+
+    fn deserialize_identifier<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+	visitor.visit_u32( self.read_u32()? )
+    }
+
+    fn deserialize_ignored_any<V: de::Visitor<'de>>(self, _: V) -> Result<V::Value> {
+	Err(Error( "link tag encoding cannot skip values".to_string() ))
+    }
+
+    fn is_human_readable(&self) -> bool {
+	false
+    }
+}
+
+
+/// Sequence and map items that are each preceded by a marker byte
+struct MarkedAccess<'a, 'de> {
+    de: &'a mut TagDeserializer<'de>,
+}
+
+impl<'de, 'a> de::SeqAccess<'de> for MarkedAccess<'a, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+	match self.de.read_marker()? {
+	    true => seed.deserialize( &mut *self.de ).map( Some ),
+	    false => Ok( None ),
+	}
+    }
+}
+
+impl<'de, 'a> de::MapAccess<'de> for MarkedAccess<'a, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+	de::SeqAccess::next_element_seed( self, seed )
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+	seed.deserialize( &mut *self.de )
+    }
+}
+
+
+/// Tuple and struct fields which are concatenated without markers
+struct FixedAccess<'a, 'de> {
+    de: &'a mut TagDeserializer<'de>,
+    remaining: usize,
+}
+
+impl<'de, 'a> de::SeqAccess<'de> for FixedAccess<'a, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+	if self.remaining == 0 {
+	    return Ok( None );
+	}
+	self.remaining -= 1;
+	seed.deserialize( &mut *self.de ).map( Some )
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+	Some( self.remaining )
+    }
+}
+
+
+impl<'de> de::EnumAccess<'de> for &mut TagDeserializer<'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self)> {
+	let index = self.read_u32()?;
+	let variant = seed.deserialize( IntoDeserializer::<Error>::into_deserializer( index ) )?;
+
+	Ok( (variant, self) )
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for &mut TagDeserializer<'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+	Ok(())
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+	seed.deserialize( self )
+    }
+
+    fn tuple_variant<V: de::Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
+	de::Deserializer::deserialize_tuple( self, len, visitor )
+    }
+
+    fn struct_variant<V: de::Visitor<'de>>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value> {
+	de::Deserializer::deserialize_tuple( self, fields.len(), visitor )
+    }
+}
+
+
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+    enum Kind {
+	Draft,
+	Published(u64),
+	Archived { reason: Option<String> },
+    }
+
+    #[derive(Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+    struct CategoryTag {
+	category: String,
+	rank: i32,
+	kind: Kind,
+	labels: Vec<String>,
+    }
+
+    fn tag(category: &str, rank: i32, kind: Kind) -> CategoryTag {
+	CategoryTag {
+	    category: category.to_string(),
+	    rank,
+	    kind,
+	    labels: vec![ "a\0b".to_string(), "".to_string() ],
+	}
+    }
+
+    #[test]
+    fn tag_roundtrip_test() {
+	let values = vec![
+	    tag( "rust", -5, Kind::Draft ),
+	    tag( "", i32::MAX, Kind::Published( 42 ) ),
+	    tag( "\0\u{1}", i32::MIN, Kind::Archived { reason: Some("old".to_string()) } ),
+	    tag( "holochain", 0, Kind::Archived { reason: None } ),
+	];
+
+	for value in values {
+	    let encoded = encode_tag( &value ).unwrap();
+	    assert_eq!( decode_tag::<CategoryTag>( &encoded ).unwrap(), value );
+	}
+
+	let floats = vec![ f64::NEG_INFINITY, -1.5, -0.0, 0.0, 2.25, f64::MAX ];
+	let encoded = encode_tag( &floats ).unwrap();
+	assert_eq!( decode_tag::<Vec<f64>>( &encoded ).unwrap(), floats );
+
+	let encoded = encode_tag( &("rust", 1u8) ).unwrap();
+	assert!( matches!( decode_tag::<String>( &encoded ), Err(UtilsError::LinkTagError(_)) ) );
+    }
+
+    #[test]
+    fn tag_ordering_test() {
+	let values = vec![
+	    tag( "", 0, Kind::Draft ),
+	    tag( "rust", i32::MIN, Kind::Draft ),
+	    tag( "rust", -1, Kind::Published( 9 ) ),
+	    tag( "rust", 0, Kind::Draft ),
+	    tag( "rust", 0, Kind::Published( 1 ) ),
+	    tag( "rust", 0, Kind::Published( 256 ) ),
+	    tag( "rust", 0, Kind::Archived { reason: None } ),
+	    tag( "rust", 0, Kind::Archived { reason: Some("".to_string()) } ),
+	    tag( "rust\0", 0, Kind::Draft ),
+	    tag( "rusty", 0, Kind::Draft ),
+	];
+
+	let encoded : Vec<LinkTag> = values.iter()
+	    .map( |value| encode_tag( value ).unwrap() )
+	    .collect();
+
+	for pair in encoded.windows( 2 ) {
+	    assert!( pair[0].0 < pair[1].0, "{:?} >= {:?}", pair[0], pair[1] );
+	}
+
+	let floats = [ f64::NEG_INFINITY, -1.5, -0.0, 0.0, 2.25, f64::MAX ];
+	for pair in floats.windows( 2 ) {
+	    assert!( encode_tag( &pair[0] ).unwrap().0 < encode_tag( &pair[1] ).unwrap().0 );
+	}
+    }
+
+    #[test]
+    fn tag_prefix_test() {
+	let value = tag( "rust", 3, Kind::Draft );
+	let encoded = encode_tag( &value ).unwrap();
+
+	let prefix = encode_tag( &("rust",) ).unwrap();
+	assert!( encoded.0.starts_with( &prefix.0 ) );
+
+	let prefix = encode_tag( &("rust", 3i32) ).unwrap();
+	assert!( encoded.0.starts_with( &prefix.0 ) );
+
+	let prefix = encode_tag( &("rus",) ).unwrap();
+	assert!( !encoded.0.starts_with( &prefix.0 ) );
+    }
+}
@@ -0,0 +1,299 @@
+use hdk::prelude::*;
+use hdk::hash_path::path::Component;
+use crate::entities::{ Entity, EntryModel };
+use crate::errors::{ UtilsResult };
+use crate::tags::{ encode_tag, decode_tag };
+use crate::options::ReadOptions;
+use crate::utils::{ now, path_from_collection, RecordSource };
+
+
+/// The smallest bucket in a [`TimeIndex`]'s path hierarchy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum TimeBucket {
+    Year,
+    Month,
+    Day,
+    Hour,
+    Minute,
+}
+
+impl TimeBucket {
+    fn depth(&self) -> usize {
+	*self as usize + 1
+    }
+}
+
+
+/// A named collection of entities bucketed by time
+///
+/// Entities are linked from the path `[name, year, month, day, hour, minute]` truncated to the
+/// configured bucket, eg. `["activity", "2023", "4", "17"]` for [`TimeBucket::Day`].  Each link's
+/// tag is the indexed timestamp (milliseconds) encoded with [`encode_tag`].
+#[derive(Debug, Clone)]
+pub struct TimeIndex<LT> {
+    /// The root segment of the index's paths
+    pub name: String,
+
+    /// The link type used for the bucket paths and the entity links
+    pub link_type: LT,
+
+    /// The smallest bucket
+    pub bucket: TimeBucket,
+}
+
+impl<LT> TimeIndex<LT> {
+    pub fn new(name: &str, link_type: LT, bucket: TimeBucket) -> Self {
+	TimeIndex {
+	    name: name.to_string(),
+	    link_type,
+	    bucket,
+	}
+    }
+
+    /// The path segments of the bucket that contains the given timestamp (milliseconds)
+    pub fn segments(&self, timestamp: u64) -> Vec<String> {
+	let mut segments = vec![ self.name.to_owned() ];
+	segments.extend( bucket_parts( timestamp, self.bucket ).iter().map( |part| part.to_string() ) );
+	segments
+    }
+}
+
+
+impl<T,I> Entity<T,I>
+where
+    I: Clone + Into<AnyLinkableHash>,
+{
+    /// Link this entity into the time index using the current time
+    pub fn link_from_time_index<LT,E>(&self, index: &TimeIndex<LT>) -> UtilsResult<ActionHash>
+    where
+	LT: Clone,
+	ScopedLinkType: TryFrom<LT, Error = E>,
+        WasmError: From<E>,
+    {
+	self.link_from_time_index_at( index, now()? )
+    }
+
+    /// Link this entity into the time index at the given timestamp (milliseconds)
+    pub fn link_from_time_index_at<LT,E>(&self, index: &TimeIndex<LT>, timestamp: u64) -> UtilsResult<ActionHash>
+    where
+	LT: Clone,
+	ScopedLinkType: TryFrom<LT, Error = E>,
+        WasmError: From<E>,
+    {
+	self.link_from_path(
+	    index.segments( timestamp ),
+	    index.link_type.to_owned(),
+	    Some( encode_tag( &timestamp )?.into_inner() ),
+	)
+    }
+}
+
+
+/// Get up to `limit` entities indexed between `from` and `to` (inclusive, milliseconds)
+///
+/// The index is walked from `from` towards `to`, so the results are oldest-first when
+/// `from <= to` and newest-first when `from > to`.  Only the buckets that exist and overlap the
+/// range are visited.  Links that cannot be resolved are skipped.
+pub fn get_entities_in_range<T,LT,ET>(index: &TimeIndex<LT>, from: u64, to: u64, limit: usize) -> UtilsResult<Vec<Entity<T>>>
+where
+    T: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    LT: LinkTypeFilterExt + Clone,
+    Entry: TryFrom<T, Error = WasmError>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    get_entities_in_range_with( index, from, to, limit, &ReadOptions::default() )
+}
+
+/// Get up to `limit` entities indexed between `from` and `to` using the given [`ReadOptions`]
+pub fn get_entities_in_range_with<T,LT,ET>(index: &TimeIndex<LT>, from: u64, to: u64, limit: usize, options: &ReadOptions) -> UtilsResult<Vec<Entity<T>>>
+where
+    T: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+    LT: LinkTypeFilterExt + Clone,
+    Entry: TryFrom<T, Error = WasmError>,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    let walk = RangeWalk {
+	index,
+	low: from.min( to ),
+	high: from.max( to ),
+	newest_first: from > to,
+	limit,
+	options: *options,
+	source: RecordSource::new( options.strategy )?,
+    };
+    let mut results = Vec::new();
+
+    if limit > 0 {
+	walk.visit( &mut Vec::new(), &mut results )?;
+    }
+
+    Ok( results )
+}
+
+
+struct RangeWalk<'a, LT> {
+    index: &'a TimeIndex<LT>,
+    low: u64,
+    high: u64,
+    newest_first: bool,
+    limit: usize,
+    options: ReadOptions<'a>,
+    source: RecordSource,
+}
+
+impl<LT> RangeWalk<'_, LT>
+where
+    LT: LinkTypeFilterExt + Clone,
+{
+    fn base(&self, parts: &[u32]) -> UtilsResult<EntryHash> {
+	let mut segments = vec![ self.index.name.to_owned() ];
+	segments.extend( parts.iter().map( |part| part.to_string() ) );
+
+	Ok( path_from_collection( segments )?.path_entry_hash()? )
+    }
+
+    fn visit<T,ET>(&self, parts: &mut Vec<u32>, results: &mut Vec<Entity<T>>) -> UtilsResult<()>
+    where
+	T: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+	Entry: TryFrom<T, Error = WasmError>,
+	ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+    {
+	let links = get_links( self.base( parts )?, self.index.link_type.to_owned(), None )?;
+
+	if parts.len() == self.index.bucket.depth() {
+	    return self.collect( links, results );
+	}
+
+	let level = parts.len();
+	let low = bucket_parts( self.low, self.index.bucket );
+	let high = bucket_parts( self.high, self.index.bucket );
+
+	let mut children : Vec<u32> = links.iter()
+	    .filter_map( |link| component_number( &link.tag ) )
+	    .filter( |child| {
+		let mut prefix = parts.to_owned();
+		prefix.push( *child );
+		&low[..=level] <= prefix.as_slice() && prefix.as_slice() <= &high[..=level]
+	    })
+	    .collect();
+	children.sort_unstable();
+	children.dedup();
+
+	if self.newest_first {
+	    children.reverse();
+	}
+
+	for child in children {
+	    parts.push( child );
+	    self.visit( parts, results )?;
+	    parts.pop();
+
+	    if results.len() >= self.limit {
+		break;
+	    }
+	}
+
+	Ok(())
+    }
+
+    fn collect<T,ET>(&self, links: Vec<Link>, results: &mut Vec<Entity<T>>) -> UtilsResult<()>
+    where
+	T: TryFrom<Record, Error = WasmError> + Clone + EntryModel<ET>,
+	Entry: TryFrom<T, Error = WasmError>,
+	ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+    {
+	let mut links : Vec<(u64, Link)> = links.into_iter()
+	    .filter_map( |link| Some( (decode_tag::<u64>( &link.tag ).ok()?, link) ) )
+	    .filter( |(timestamp, _)| self.low <= *timestamp && *timestamp <= self.high )
+	    .collect();
+	links.sort_by( |(a, a_link), (b, b_link)| {
+	    ( a, &a_link.create_link_hash ).cmp( &( b, &b_link.create_link_hash ) )
+	});
+
+	if self.newest_first {
+	    links.reverse();
+	}
+
+	for (_, link) in links {
+	    if results.len() >= self.limit {
+		break;
+	    }
+
+	    if let Some(entity) = link.target.into_entry_hash()
+		.and_then( |target| crate::get_entity_from( &target, &self.options, &self.source ).ok() )
+	    {
+		results.push( entity );
+	    }
+	}
+
+	Ok(())
+    }
+}
+
+
+/// Decode the number in a bucket path's child link tag (see [`Path::make_tag`])
+fn component_number(tag: &LinkTag) -> Option<u32> {
+    let component : Component = SerializedBytes::from( UnsafeBytes::from( tag.0.to_owned() ) )
+	.try_into().ok()?;
+
+    String::try_from( &component ).ok()?
+	.parse().ok()
+}
+
+
+/// The `[year, month, day, hour, minute]` (UTC) of a timestamp in milliseconds truncated to the
+/// given bucket
+fn bucket_parts(timestamp: u64, bucket: TimeBucket) -> Vec<u32> {
+    let minutes = timestamp / 60_000;
+    let days = minutes / 1440;
+    let ( year, month, day ) = civil_from_days( days );
+
+    let parts = [
+	year,
+	month,
+	day,
+	( minutes % 1440 / 60 ) as u32,
+	( minutes % 60 ) as u32,
+    ];
+
+    parts[..bucket.depth()].to_vec()
+}
+
+// Days since the unix epoch to a (year, month, day) date; see
+// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: u64) -> (u32, u32, u32) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = ( doe - doe / 1460 + doe / 36_524 - doe / 146_096 ) / 365;
+    let doy = doe - ( 365 * yoe + yoe / 4 - yoe / 100 );
+    let mp = ( 5 * doy + 2 ) / 153;
+    let day = doy - ( 153 * mp + 2 ) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    ( year as u32, month as u32, day as u32 )
+}
+
+
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_parts_test() {
+	assert_eq!( bucket_parts( 0, TimeBucket::Minute ), vec![ 1970, 1, 1, 0, 0 ] );
+
+	// 2000-02-29T23:59:59.999Z
+	assert_eq!( bucket_parts( 951_868_799_999, TimeBucket::Minute ), vec![ 2000, 2, 29, 23, 59 ] );
+	assert_eq!( bucket_parts( 951_868_800_000, TimeBucket::Day ), vec![ 2000, 3, 1 ] );
+
+	// 2023-12-31T12:30:00Z
+	assert_eq!( bucket_parts( 1_704_025_800_000, TimeBucket::Hour ), vec![ 2023, 12, 31, 12 ] );
+	assert_eq!( bucket_parts( 1_704_025_800_000, TimeBucket::Year ), vec![ 2023 ] );
+
+	let index = TimeIndex::new( "activity", (), TimeBucket::Month );
+	assert_eq!( index.segments( 1_704_025_800_000 ), vec![ "activity", "2023", "12" ] );
+    }
+}
@@ -1,5 +1,8 @@
+use std::collections::HashSet;
 use hdk::prelude::*;
+use holo_hash::AnyDhtHashPrimitive;
 use crate::entities::{ EntryModel };
+use crate::options::{ ReadOptions, ReadStrategy };
 use crate::errors::{ UtilsResult, UtilsError };
 
 /// Get the current unix timestamp
@@ -45,13 +48,43 @@ where
     T: TryFrom<Record, Error = WasmError> + Clone,
     ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
 {
-    let content = T::try_from( record.clone() )
-	.map_err(|_| UtilsError::DeserializationError( T::name(), record.action().entry_type().map(|et| et.to_owned())) )?;
+    Ok( to_entry_type_versioned( record )?.0 )
+}
+
+/// Same as [`to_entry_type`] but earlier content shapes are upgraded using
+/// [`EntryModel::upgrades`]
+///
+/// Returns the content along with the version it was stored as, or `None` if the record can be
+/// decoded as more than one shape (see [`Upgrade`](crate::Upgrade)).
+pub fn to_entry_type_versioned<T,ET>(record: Record) -> UtilsResult<(T, Option<u32>)>
+where
+    T: EntryModel<ET>,
+    T: TryFrom<Record, Error = WasmError> + Clone,
+    ScopedEntryDefIndex: for<'a> TryFrom<&'a ET, Error = WasmError>,
+{
+    let mut upgrades = T::upgrades();
+    upgrades.sort_by_key( |upgrade| std::cmp::Reverse( upgrade.version ) );
+
+    let (content, version) = match T::try_from( record.clone() ) {
+	Ok(content) => {
+	    let ambiguous = upgrades.iter().any( |upgrade| upgrade.matches( &record ) );
+	    (content, (!ambiguous).then( T::version ))
+	},
+	Err(_) => {
+	    let not_decoded = || UtilsError::DeserializationError( T::name(), record.action().entry_type().map(|et| et.to_owned()));
+	    let mut decoded = upgrades.iter()
+		.filter( |upgrade| upgrade.matches( &record ) );
+	    let upgrade = decoded.next().ok_or_else( not_decoded )?;
+	    let content = upgrade.apply( &record ).ok_or_else( not_decoded )?;
+
+	    (content, decoded.next().is_none().then_some( upgrade.version ))
+	},
+    };
     let scoped_def = ScopedEntryDefIndex::try_from( &content.to_input() )?;
 
     if let Some(EntryType::App(AppEntryDef {zome_index, entry_index, ..})) = record.action().entry_type() {
 	if *zome_index == scoped_def.zome_index && *entry_index == scoped_def.zome_type {
-	    Ok(content)
+	    Ok( (content, version) )
 	}
 	else {
 	    Err(UtilsError::WrongEntryTypeError(scoped_def.zome_index, scoped_def.zome_type, zome_index.to_owned(), entry_index.to_owned()))?
@@ -79,30 +112,166 @@ where
 }
 
 
-fn trace_action_history_with_chain(action_hash: &ActionHash, history: Option<Vec<(ActionHash,EntryHash)>>) -> UtilsResult<Vec<(ActionHash,EntryHash)>> {
-    let sh_action = must_get_action( action_hash.to_owned().into() )?;
-    let mut history = history.unwrap_or( Vec::new() );
+/// Follow the Action's origin until we find the Create Action.
+pub fn trace_action_history(action_hash: &ActionHash) -> UtilsResult<Vec<(ActionHash,EntryHash)>> {
+    trace_action_history_with( action_hash, &ReadOptions::default() )
+}
 
-    match sh_action.action() {
-	Action::Create(create) => {
-	    history.push( (action_hash.to_owned(), create.entry_hash.to_owned()) );
+/// Follow the Action's origin until we find the Create Action, visiting at most
+/// [`ReadOptions::max_depth`] actions.
+pub fn trace_action_history_with(action_hash: &ActionHash, options: &ReadOptions) -> UtilsResult<Vec<(ActionHash,EntryHash)>> {
+    let mut history = Vec::new();
+    let mut visited = HashSet::new();
+    let mut next = action_hash.to_owned();
 
-	    Ok( history )
-	},
-	Action::Update(update) => {
-	    history.push( (action_hash.to_owned(), update.entry_hash.to_owned()) );
+    loop {
+	if history.len() >= options.max_depth {
+	    Err(UtilsError::HistoryTooDeep(action_hash.to_owned(), options.max_depth))?;
+	}
+	if !visited.insert( next.to_owned() ) {
+	    Err(UtilsError::HistoryCycle(next.to_owned()))?;
+	}
 
-	    trace_action_history_with_chain( &update.original_action_address, Some(history) )
-	},
-	action => Err(wasm_error!(WasmErrorInner::Guest(format!("Unexpected action type @ trace depth {}: {:?}", history.len(), action ))))?,
+	let sh_action = must_get_action( next.to_owned() )?;
+
+	match sh_action.action() {
+	    Action::Create(create) => {
+		history.push( (next, create.entry_hash.to_owned()) );
+
+		return Ok( history );
+	    },
+	    Action::Update(update) => {
+		history.push( (next, update.entry_hash.to_owned()) );
+
+		next = update.original_action_address.to_owned();
+	    },
+	    action => Err(wasm_error!(WasmErrorInner::Guest(format!("Unexpected action type @ trace depth {}: {:?}", history.len(), action ))))?,
+	}
     }
 }
 
-/// Follow the Action's origin until we find the Create Action.
-pub fn trace_action_history(action_hash: &ActionHash) -> UtilsResult<Vec<(ActionHash,EntryHash)>> {
-    trace_action_history_with_chain(action_hash, None)
+
+/// Where the records for a single read come from
+///
+/// A [`ReadStrategy::Local`] source queries the source chain once when it is created and every
+/// lookup made through it reuses that snapshot.
+pub(crate) enum RecordSource {
+    Network(GetOptions),
+    Local(Vec<Record>),
+}
+
+impl RecordSource {
+    pub(crate) fn new(strategy: ReadStrategy) -> UtilsResult<Self> {
+	Ok( match strategy {
+	    ReadStrategy::Local => RecordSource::Local(
+		query( ChainQueryFilter::new().include_entries( true ) )?
+	    ),
+	    ReadStrategy::Latest => RecordSource::Network( GetOptions::latest() ),
+	    ReadStrategy::Content => RecordSource::Network( GetOptions::content() ),
+	})
+    }
+
+    /// Get a Record from this source
+    pub(crate) fn get<H>(&self, hash: H) -> UtilsResult<Option<Record>>
+    where
+	H: Into<AnyDhtHash>,
+    {
+	match self {
+	    RecordSource::Local(records) => Ok( local_record( records, hash.into() ) ),
+	    RecordSource::Network(options) => Ok( get( hash.into(), options.to_owned() )? ),
+	}
+    }
+
+    /// Get the Details for a hash from this source
+    pub(crate) fn get_details<H>(&self, hash: H) -> UtilsResult<Option<Details>>
+    where
+	H: Into<AnyDhtHash>,
+    {
+	match self {
+	    RecordSource::Local(records) => Ok( local_details( records, hash.into() ) ),
+	    RecordSource::Network(options) => Ok( get_details( hash.into(), options.to_owned() )? ),
+	}
+    }
+}
+
+fn local_record(records: &[Record], hash: AnyDhtHash) -> Option<Record> {
+    match hash.into_primitive() {
+	AnyDhtHashPrimitive::Action(hash) => records.iter()
+	    .find( |record| record.action_address() == &hash )
+	    .cloned(),
+	AnyDhtHashPrimitive::Entry(hash) => {
+	    let deleted : HashSet<ActionHash> = records.iter()
+		.filter_map( |record| match record.action() {
+		    Action::Delete(delete) => Some( delete.deletes_address.to_owned() ),
+		    _ => None,
+		})
+		.collect();
+
+	    records.iter()
+		.find( |record| {
+		    record.action().entry_hash() == Some(&hash)
+			&& !deleted.contains( record.action_address() )
+		})
+		.cloned()
+	},
+    }
 }
 
+fn local_details(records: &[Record], hash: AnyDhtHash) -> Option<Details> {
+    match hash.into_primitive() {
+	AnyDhtHashPrimitive::Action(hash) => {
+	    let deletes = records.iter()
+		.filter( |record| matches!( record.action(), Action::Delete(delete) if delete.deletes_address == hash ) )
+		.map( |record| record.signed_action().to_owned() )
+		.collect();
+	    let updates = records.iter()
+		.filter( |record| matches!( record.action(), Action::Update(update) if update.original_action_address == hash ) )
+		.map( |record| record.signed_action().to_owned() )
+		.collect();
+
+	    records.iter()
+		.find( |record| record.action_address() == &hash )
+		.map( |record| Details::Record(RecordDetails {
+		    record: record.to_owned(),
+		    validation_status: ValidationStatus::Valid,
+		    deletes,
+		    updates,
+		}))
+	},
+	AnyDhtHashPrimitive::Entry(hash) => {
+	    let creates : Vec<&Record> = records.iter()
+		.filter( |record| matches!( record.action(), Action::Create(_) | Action::Update(_) ) )
+		.filter( |record| record.action().entry_hash() == Some(&hash) )
+		.collect();
+	    let entry = creates.iter().find_map( |record| record.entry().as_option() )?.to_owned();
+	    let actions : Vec<SignedActionHashed> = creates.iter()
+		.map( |record| record.signed_action().to_owned() )
+		.collect();
+	    let deletes : Vec<SignedActionHashed> = records.iter()
+		.filter( |record| matches!( record.action(), Action::Delete(delete) if delete.deletes_entry_address == hash ) )
+		.map( |record| record.signed_action().to_owned() )
+		.collect();
+	    let updates = records.iter()
+		.filter( |record| matches!( record.action(), Action::Update(update) if update.original_entry_address == hash ) )
+		.map( |record| record.signed_action().to_owned() )
+		.collect();
+	    let is_live = actions.iter()
+		.any( |sh| !deletes.iter().any( |delete| matches!( delete.action(), Action::Delete(delete) if &delete.deletes_address == sh.action_address() ) ) );
+
+	    Some( Details::Entry(EntryDetails {
+		entry,
+		actions,
+		rejected_actions: Vec::new(),
+		deletes,
+		updates,
+		entry_dht_status: match is_live {
+		    true => EntryDhtStatus::Live,
+		    false => EntryDhtStatus::Dead,
+		},
+	    }))
+	},
+    }
+}
 
 
 #[cfg(test)]
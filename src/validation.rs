@@ -0,0 +1,280 @@
+//! Validation helpers for integrity zomes that store CEPS entities
+//!
+//! [`update_entity`](crate::update_entity) assumes that every Update leads back to a Create of the
+//! same entry type and that every Delete targets a Create.  Any agent may update or delete an
+//! entity unless the zome opts in to author checks (see
+//! [`UpdateOptions::author_only`](crate::UpdateOptions::author_only)), so the same-author rule is
+//! only enforced when [`ValidationOptions::author_only`] is set.
+//!
+//! These helpers only use `hdi` functions (`must_get_*`) so they can run in an integrity zome's
+//! `validate` callback.
+//!
+//! ```ignore
+//! #[hdk_extern]
+//! pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
+//!     hc_crud::validate_ceps_op_with( &op, &ValidationOptions::author_only() )
+//! }
+//! ```
+
+use std::collections::HashSet;
+use hdi::prelude::*;
+use crate::options::{ ValidationOptions, DEFAULT_MAX_DEPTH };
+
+
+/// Validate the CEPS rules for any Update or Delete carried by the given op
+///
+/// Any agent may update or delete an entity; use [`validate_ceps_op_with`] and
+/// [`ValidationOptions::author_only`] to also require the original author.  Ops that are not an
+/// Update or Delete are [`ValidateCallbackResult::Valid`].
+pub fn validate_ceps_op(op: &Op) -> ExternResult<ValidateCallbackResult> {
+    validate_ceps_op_with( op, &ValidationOptions::default() )
+}
+
+/// Validate the CEPS rules chosen by the given [`ValidationOptions`]
+pub fn validate_ceps_op_with(op: &Op, options: &ValidationOptions) -> ExternResult<ValidateCallbackResult> {
+    match op {
+	Op::StoreRecord(StoreRecord { record }) => match record.action() {
+	    Action::Update(update) => check_update( update, options ),
+	    Action::Delete(delete) => check_delete( delete, options ),
+	    _ => Ok( ValidateCallbackResult::Valid ),
+	},
+	Op::RegisterUpdate(RegisterUpdate { update, .. }) => check_update( update.hashed.as_content(), options ),
+	Op::RegisterDelete(RegisterDelete { delete, .. }) => check_delete( delete.hashed.as_content(), options ),
+	_ => Ok( ValidateCallbackResult::Valid ),
+    }
+}
+
+/// Validate that an Update leads back to a Create of the same entry type made by the same author
+pub fn validate_update(update: &Update) -> ExternResult<ValidateCallbackResult> {
+    let (create_hash, create) = match trace_origin( update )? {
+	Ok(origin) => origin,
+	Err(reason) => return Ok( ValidateCallbackResult::Invalid(reason) ),
+    };
+
+    Ok( same_author( &update.author, &create_hash, &create.author ) )
+}
+
+/// Validate that an Update leads back to a Create of the same entry type
+///
+/// Unlike [`validate_update`], any agent may update the entity.
+pub fn validate_update_chain(update: &Update) -> ExternResult<ValidateCallbackResult> {
+    Ok( match trace_origin( update )? {
+	Ok(_) => ValidateCallbackResult::Valid,
+	Err(reason) => ValidateCallbackResult::Invalid(reason),
+    })
+}
+
+/// Validate that a Delete targets an entity's Create action and is made by the same author
+pub fn validate_delete(delete: &Delete) -> ExternResult<ValidateCallbackResult> {
+    Ok( match deleted_create( delete )? {
+	Ok(create) => same_author( &delete.author, &delete.deletes_address, &create.author ),
+	Err(reason) => ValidateCallbackResult::Invalid(reason),
+    })
+}
+
+/// Validate that a Delete targets an entity's Create action
+///
+/// Unlike [`validate_delete`], any agent may delete the entity.
+pub fn validate_delete_target(delete: &Delete) -> ExternResult<ValidateCallbackResult> {
+    Ok( match deleted_create( delete )? {
+	Ok(_) => ValidateCallbackResult::Valid,
+	Err(reason) => ValidateCallbackResult::Invalid(reason),
+    })
+}
+
+
+fn check_update(update: &Update, options: &ValidationOptions) -> ExternResult<ValidateCallbackResult> {
+    match options.author_only {
+	true => validate_update( update ),
+	false => validate_update_chain( update ),
+    }
+}
+
+fn check_delete(delete: &Delete, options: &ValidationOptions) -> ExternResult<ValidateCallbackResult> {
+    match options.author_only {
+	true => validate_delete( delete ),
+	false => validate_delete_target( delete ),
+    }
+}
+
+fn same_author(author: &AgentPubKey, create_hash: &ActionHash, create_author: &AgentPubKey) -> ValidateCallbackResult {
+    match author == create_author {
+	true => ValidateCallbackResult::Valid,
+	false => ValidateCallbackResult::Invalid(format!(
+	    "Author {} does not match the author {} of Create action {}",
+	    author, create_author, create_hash,
+	)),
+    }
+}
+
+/// Get the Create action a Delete targets
+///
+/// The inner `Err` is the reason the Delete is invalid.
+fn deleted_create(delete: &Delete) -> ExternResult<Result<Create, String>> {
+    let deleted = must_get_action( delete.deletes_address.to_owned() )?;
+
+    Ok( match deleted.action() {
+	Action::Create(create) => Ok( create.to_owned() ),
+	action => Err(format!(
+	    "Delete must target a Create action but {} is a {} action",
+	    delete.deletes_address, action.action_type(),
+	)),
+    })
+}
+
+/// Follow an Update's original actions back to its Create
+///
+/// The inner `Err` is the reason the update chain is invalid.
+fn trace_origin(update: &Update) -> ExternResult<Result<(ActionHash, Create), String>> {
+    let original = must_get_action( update.original_action_address.to_owned() )?;
+
+    // Actions without an entry are rejected by the chain walk below
+    if matches!( original.action().entry_hash(), Some(hash) if hash != &update.original_entry_address ) {
+	return Ok( Err(format!(
+	    "Update's original entry address {} does not match the entry of action {}",
+	    update.original_entry_address, update.original_action_address,
+	)) );
+    }
+
+    let mut visited = HashSet::new();
+    let mut current = original;
+
+    loop {
+	let hash = current.action_address().to_owned();
+
+	if visited.len() >= DEFAULT_MAX_DEPTH {
+	    return Ok( Err(format!("Update chain exceeded the maximum depth of {} at action {}", DEFAULT_MAX_DEPTH, hash )) );
+	}
+	if !visited.insert( hash.to_owned() ) {
+	    return Ok( Err(format!("Update chain contains a cycle at action {}", hash )) );
+	}
+
+	let entry_type = current.action().entry_type();
+
+	current = match current.action() {
+	    Action::Create(_) | Action::Update(_) if entry_type != Some(&update.entry_type) => return Ok( Err(format!(
+		"Update has entry type {:?} but action {} in its chain has entry type {:?}",
+		update.entry_type, hash, entry_type,
+	    )) ),
+	    Action::Create(create) => return Ok( Ok( (hash, create.to_owned()) ) ),
+	    Action::Update(previous) => must_get_action( previous.original_action_address.to_owned() )?,
+	    action => return Ok( Err(format!(
+		"Update chain must lead to a Create action but {} is a {} action",
+		hash, action.action_type(),
+	    )) ),
+	};
+    }
+}
+
+
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{ create_entity, update_entity, delete_entity };
+    use crate::tests::{ setup, post, comment, PostEntry, EntryTypes, LinkTypes };
+
+    fn update_action(hash: &ActionHash) -> Update {
+	match must_get_action( hash.to_owned() ).unwrap().action() {
+	    Action::Update(update) => update.to_owned(),
+	    action => panic!("Expected an Update action; found {:?}", action ),
+	}
+    }
+
+    fn delete_action(hash: &ActionHash) -> Delete {
+	match must_get_action( hash.to_owned() ).unwrap().action() {
+	    Action::Delete(delete) => delete.to_owned(),
+	    action => panic!("Expected a Delete action; found {:?}", action ),
+	}
+    }
+
+    fn is_invalid(result: ExternResult<ValidateCallbackResult>, reason: &str) -> bool {
+	matches!( result.unwrap(), ValidateCallbackResult::Invalid(message) if message.contains( reason ) )
+    }
+
+    #[test]
+    fn validate_update_test() {
+	let dht = setup();
+	let alice = dht.agent();
+	let bobby = AgentPubKey::from_raw_32( vec![2; 32] );
+
+	let created = create_entity( &post("first") ).unwrap();
+	let first = update_entity( &created.action, |_: PostEntry, _| Ok( post("second") ) ).unwrap();
+	let second = update_entity( &first.action, |_: PostEntry, _| Ok( post("third") ) ).unwrap();
+
+	let update = update_action( &second.action );
+	assert_eq!( validate_update( &update ).unwrap(), ValidateCallbackResult::Valid );
+
+	let record = must_get_valid_record( second.action.to_owned() ).unwrap();
+	assert_eq!( validate_ceps_op( &Op::StoreRecord(StoreRecord { record }) ).unwrap(), ValidateCallbackResult::Valid );
+
+	dht.set_agent( bobby );
+	let by_bobby = update_entity( &second.action, |_: PostEntry, _| Ok( post("fourth") ) ).unwrap();
+	let update = update_action( &by_bobby.action );
+	assert!( is_invalid( validate_update( &update ), "does not match the author" ) );
+	assert_eq!( validate_update_chain( &update ).unwrap(), ValidateCallbackResult::Valid );
+
+	let op = Op::StoreRecord(StoreRecord { record: must_get_valid_record( by_bobby.action.to_owned() ).unwrap() });
+	assert_eq!( validate_ceps_op( &op ).unwrap(), ValidateCallbackResult::Valid );
+	assert!( is_invalid( validate_ceps_op_with( &op, &ValidationOptions::author_only() ), "does not match the author" ) );
+	dht.set_agent( alice );
+
+	let reply = create_entity( &comment("reply") ).unwrap();
+	let comment_type = must_get_action( reply.action.to_owned() ).unwrap().action().entry_type().unwrap().to_owned();
+
+	let mut forged = update_action( &first.action );
+	forged.entry_type = comment_type;
+	assert!( is_invalid( validate_update( &forged ), "entry type" ) );
+
+	let mut forged = update_action( &first.action );
+	forged.original_entry_address = reply.address.to_owned();
+	assert!( is_invalid( validate_update( &forged ), "original entry address" ) );
+    }
+
+    #[test]
+    fn validate_update_origin_test() {
+	setup();
+
+	let created = create_entity( &post("first") ).unwrap();
+	let first = update_entity( &created.action, |_: PostEntry, _| Ok( post("second") ) ).unwrap();
+	let reply = create_entity( &comment("reply") ).unwrap();
+	let link_hash = reply.link_from( &created.id, LinkTypes::Comment, None ).unwrap();
+
+	let mut forged = update_action( &first.action );
+	forged.original_action_address = link_hash;
+	assert!( is_invalid( validate_update( &forged ), "must lead to a Create action" ) );
+	assert!( is_invalid( validate_update_chain( &forged ), "must lead to a Create action" ) );
+
+	let delete_hash = delete_entity::<PostEntry,EntryTypes>( &created.id ).unwrap();
+	let mut forged = update_action( &first.action );
+	forged.original_action_address = delete_hash;
+	assert!( is_invalid( validate_update( &forged ), "must lead to a Create action" ) );
+    }
+
+    #[test]
+    fn validate_delete_test() {
+	let dht = setup();
+	let alice = dht.agent();
+	let bobby = AgentPubKey::from_raw_32( vec![2; 32] );
+
+	let created = create_entity( &post("first") ).unwrap();
+	let updated = update_entity( &created.action, |_: PostEntry, _| Ok( post("second") ) ).unwrap();
+
+	let delete = delete_action( &delete_entity::<PostEntry,EntryTypes>( &created.id ).unwrap() );
+	assert_eq!( validate_delete( &delete ).unwrap(), ValidateCallbackResult::Valid );
+
+	let mut forged = delete.to_owned();
+	forged.deletes_address = updated.action.to_owned();
+	assert!( is_invalid( validate_delete( &forged ), "must target a Create" ) );
+	assert!( is_invalid( validate_delete_target( &forged ), "must target a Create" ) );
+
+	dht.set_agent( bobby.to_owned() );
+	let other = create_entity( &post("other") ).unwrap();
+	dht.set_agent( alice );
+
+	let mut forged = delete;
+	forged.deletes_address = other.action.to_owned();
+	assert!( is_invalid( validate_delete( &forged ), "does not match the author" ) );
+	assert_eq!( validate_delete_target( &forged ).unwrap(), ValidateCallbackResult::Valid );
+    }
+}
@@ -0,0 +1,91 @@
+use hdk::prelude::*;
+use serde::de::DeserializeOwned;
+
+
+type UpgradeFn<T> = Box<dyn Fn(&SerializedBytes) -> Option<T>>;
+type DecodesFn = fn(&SerializedBytes) -> bool;
+
+
+/// An earlier content shape of an [`EntryModel`](crate::EntryModel) and how to upgrade it
+///
+/// Returned by [`EntryModel::upgrades`](crate::EntryModel::upgrades).  A record that does not
+/// deserialize to the current shape is decoded as each earlier shape from the highest version down
+/// and the first match is upgraded.
+///
+/// The version is not stored with the entry; it is only known when exactly one shape (current or
+/// earlier) can decode the record.  Records are MessagePack maps, unknown fields are ignored and
+/// missing `Option` or `#[serde(default)]` fields are filled in, so after a field is removed or an
+/// optional field is added a record can decode as more than one shape.  Such a record is read as
+/// the newest shape that decodes it and its [`Entity::version`](crate::Entity::version) is `None`.
+///
+/// ```ignore
+/// #[derive(Deserialize)]
+/// pub struct PostEntryV1 {
+///     pub text: String,
+/// }
+///
+/// fn upgrades() -> Vec<Upgrade<Self>> {
+///     vec![
+///         Upgrade::new( 1, |old: PostEntryV1| PostEntry { message: old.text, tags: vec![] } ),
+///     ]
+/// }
+/// ```
+pub struct Upgrade<T> {
+    /// The version of the earlier shape
+    pub version: u32,
+
+    upgrade: UpgradeFn<T>,
+    decodes: DecodesFn,
+}
+
+impl<T> Upgrade<T> {
+    /// Decode content written as `Old` and convert it with the given function
+    pub fn new<Old, F>(version: u32, upgrade: F) -> Self
+    where
+	Old: DeserializeOwned,
+	F: Fn(Old) -> T + 'static,
+    {
+	Upgrade {
+	    version,
+	    upgrade: Box::new( move |bytes: &SerializedBytes| {
+		holochain_serialized_bytes::decode::<_, Shape<Old>>( bytes.bytes() ).ok()
+		    .map( |shape| upgrade( shape.0 ) )
+	    }),
+	    decodes: decodes_as::<Old>,
+	}
+    }
+
+    /// Upgrade the given record's entry if it has this earlier shape
+    pub(crate) fn apply(&self, record: &Record) -> Option<T> {
+	(self.upgrade)( app_entry_bytes( record )? )
+    }
+
+    /// Check if the given record's entry can be decoded as this earlier shape
+    pub(crate) fn matches(&self, record: &Record) -> bool {
+	matches!( app_entry_bytes( record ), Some(bytes) if (self.decodes)( bytes ) )
+    }
+}
+
+
+fn app_entry_bytes(record: &Record) -> Option<&SerializedBytes> {
+    match record.entry().as_option() {
+	Some(Entry::App(AppEntryBytes(bytes))) => Some( bytes ),
+	_ => None,
+    }
+}
+
+fn decodes_as<Old: DeserializeOwned>(bytes: &SerializedBytes) -> bool {
+    holochain_serialized_bytes::decode::<_, Shape<Old>>( bytes.bytes() ).is_ok()
+}
+
+
+/// Decodes as `Old` without requiring `Old: Debug` (which the decoder asks for)
+#[derive(Deserialize)]
+#[serde(transparent)]
+struct Shape<Old>(Old);
+
+impl<Old> std::fmt::Debug for Shape<Old> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+	write!( f, "Shape<{}>", std::any::type_name::<Old>() )
+    }
+}
@@ -32,6 +32,37 @@ CommentEntity.model("entry", content => {
 const schema				= new Architecture([ PostEntity, CommentEntity ]);
 
 
+const RUST_ESCAPES			= { "n": "\n", "r": "\r", "t": "\t", "0": "\0" };
+
+// Unescape the contents of a Rust string literal as written by its Debug format
+//
+// Code points are written as '\u{…}' with up to 6 hex digits, so they are decoded with
+// 'String.fromCodePoint' which also produces the surrogate pairs for non-BMP characters.
+function unescape_rust_string ( literal ) {
+    return literal.replace( /\\(?:u\{([0-9a-fA-F]{1,6})\}|(.))/g, (_, code, char) => {
+	if ( code !== undefined )
+	    return String.fromCodePoint( parseInt( code, 16 ) );
+
+	return RUST_ESCAPES[ char ] ?? char;
+    });
+}
+
+// Extract the UtilsError payload ({ kind, message, data }) from a failed zome call
+//
+// The payload JSON arrives inside the debug formatted 'WasmError { .. error: Guest("…") }' string,
+// so it is unescaped as a Rust string literal before it is parsed.
+function decode_utils_error ( err ) {
+    const match				= String( err.message ).match( /Guest\("((?:[^"\\]|\\.)*)"\)/ );
+
+    if ( match === null )
+	throw new TypeError(`Error does not contain a Guest payload: ${err.message}`);
+
+    return JSON.parse( unescape_rust_string( match[1] ) );
+}
+
+
 module.exports = {
     schema,
+    unescape_rust_string,
+    decode_utils_error,
 };
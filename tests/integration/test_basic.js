@@ -14,7 +14,9 @@ const { HoloHash,
 const json				= require('@whi/json');
 // const why				= require('why-is-node-running');
 
-const { schema }			= require('./setup.js');
+const { schema,
+	unescape_rust_string,
+	decode_utils_error }		= require('./setup.js');
 
 const delay				= (n) => new Promise(f => setTimeout(f, n));
 const DNA_PATH				= path.join( __dirname, "../dnas/happy_path.dna" );
@@ -33,7 +35,7 @@ async function expect_reject ( cb, error, message ) {
 
 
 let client;
-let post, post2;
+let post, post2, post2_v1;
 let comment, comment2;
 let create_post_input			= {
     "message": "Hello, world!",
@@ -72,6 +74,7 @@ function basic_tests () {
 	});
 
 	let prev_post			= post2;
+	post2_v1			= post2;
 	post2				= await client.call( "happy_path", "happy_path", "update_post", {
 	    "addr": post2.$action,
 	    "properties": input,
@@ -88,6 +91,14 @@ function basic_tests () {
 
 	expect( post2.message		).to.equal( input.message );
 	expect( post2.$action		).to.not.deep.equal( prev_post.$action );
+
+	let history			= await client.call( "happy_path", "happy_path", "get_post_history", {
+	    "id": post2.$id,
+	});
+
+	expect( history			).to.have.length( 2 );
+	expect( history[0].message	).to.equal( create_post_input.message );
+	expect( history[1].message	).to.equal( input.message );
     });
 
     it("should test 'Collection'", async function () {
@@ -181,6 +192,25 @@ function basic_tests () {
 
 	    expect( comments		).to.have.length( 1 );
 	}
+
+	{
+	    // Link a post as if it were a comment so that one link cannot be resolved
+	    await client.call( "happy_path", "happy_path", "link_comment_to_post", {
+		"comment_id": post2.$id,
+		"post_id": post2.$id,
+	    });
+
+	    let collection		= await client.call( "happy_path", "happy_path", "get_comment_collection_for_post", post2.$id );
+
+	    expect( collection.entities	).to.have.length( 1 );
+	    expect( collection.failures	).to.have.length( 1 );
+
+	    let [ link, error ]		= collection.failures[0];
+
+	    expect( new HoloHash( link.target )	).to.deep.equal( post2.$id );
+	    expect( error.kind			).to.equal( "DeserializationError" );
+	    expect( error.message		).to.have.string( "Failed to deserialize to entry type 'Comment'" );
+	}
     });
 
     it("should test 'delete_entity'", async function () {
@@ -219,13 +249,40 @@ function errors_tests () {
 	}, RibosomeError, "Deserialized entry to wrong type: expected 0/0 but found 0/1" );
     });
 
+    it("should fail to update because address is not the latest version", async function () {
+	await expect_reject( async () => {
+	    await client.call( "happy_path", "happy_path", "update_post", {
+		"addr": post2_v1.$action,
+		"properties": create_post_input,
+	    });
+	}, RibosomeError, "is not the latest version" );
+    });
+
+    it("should decode the 'StaleUpdate' payload from the error", async function () {
+	let payload;
+	try {
+	    await client.call( "happy_path", "happy_path", "update_post", {
+		"addr": post2_v1.$action,
+		"properties": create_post_input,
+	    });
+	} catch (err) {
+	    payload			= decode_utils_error( err );
+	}
+
+	expect( payload			).to.be.an("object");
+	expect( payload.kind		).to.equal( "StaleUpdate" );
+	expect( payload.message		).to.have.string( "is not the latest version" );
+	expect( new HoloHash( payload.data.given )	).to.deep.equal( post2_v1.$action );
+	expect( new HoloHash( payload.data.latest )	).to.deep.equal( post2.$action );
+    });
+
     it("should fail to create comment because post is deleted", async function () {
 	await expect_reject( async () => {
 	    await client.call( "happy_path", "happy_path", "create_comment", {
 		"post_id": post.$id,
 		"comment": create_comment_input_1,
 	    });
-	}, RibosomeError, "Record not found for Entry address" );
+	}, RibosomeError, "has been deleted by action" );
     });
 
     it("should fail to delete because wrong type", async function () {
@@ -253,6 +310,20 @@ function errors_tests () {
     });
 }
 
+describe("Error decoding", () => {
+
+    it("should decode escaped non-BMP characters in the payload", async function () {
+	// Debug output of a Guest error from Rust; U+1D173 and U+E0001 are escaped, U+1F600 is not
+	const err			= new Error( String.raw`WasmError { file: "src/lib.rs", line: 1, error: Guest("{\"kind\":\"Test\",\"message\":\"quote \\\" tab\\t nl\\n \u{1d173} 😀 \u{e0001} é \\\\u{41}\",\"data\":{}}") }` );
+	const payload			= decode_utils_error( err );
+
+	expect( payload.kind		).to.equal( "Test" );
+	expect( payload.message		).to.equal( "quote \" tab\t nl\n \u{1D173} \u{1F600} \u{E0001} \u00e9 \\u{41}" );
+	expect( unescape_rust_string( String.raw`\u{1f600}` )	).to.equal( "\u{1F600}" );
+    });
+
+});
+
 describe("CEPS", () => {
 
     const holochain			= new Holochain({
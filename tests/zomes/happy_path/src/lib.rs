@@ -2,7 +2,8 @@ use hdk::prelude::*;
 use hc_crud::{
     now,
     create_entity, get_entity, get_entities, update_entity, delete_entity,
-    Entity, EntryModel, EntityType,
+    get_entity_history, get_entity_collection,
+    Entity, EntryModel, EntityType, EntityCollection,
 };
 
 
@@ -35,23 +36,14 @@ fn init(_: ()) -> ExternResult<InitCallbackResult> {
 
 
 #[hdk_entry_helper]
-#[derive(Clone)]
+#[derive(Clone, EntryModel)]
+#[entry_model(name = "Post", type = "post", model = "entry", variant = EntryTypes::Post)]
 pub struct PostEntry {
     pub message: String,
     pub published_at: Option<u64>,
     pub last_updated: Option<u64>,
 }
 
-impl EntryModel<EntryTypes> for PostEntry {
-    fn name() -> &'static str { "Post" }
-    fn get_type(&self) -> EntityType {
-	EntityType::new( "post", "entry" )
-    }
-    fn to_input(&self) -> EntryTypes {
-	EntryTypes::Post(self.clone())
-    }
-}
-
 
 #[hdk_entry_helper]
 #[derive(Clone)]
@@ -123,6 +115,13 @@ pub fn get_post(input: GetEntityInput) -> ExternResult<Entity<PostEntry>> {
 }
 
 
+#[hdk_extern]
+pub fn get_post_history(input: GetEntityInput) -> ExternResult<Vec<Entity<PostEntry>>> {
+    debug!("Get Post history: {:?}", input.id );
+    Ok( get_entity_history( &input.id )? )
+}
+
+
 #[hdk_extern]
 pub fn update_post(mut input: UpdateEntityInput<PostEntry>) -> ExternResult<Entity<PostEntry>> {
     if input.properties.last_updated.is_none() {
@@ -188,6 +187,12 @@ pub fn get_comments_for_post(post_id: EntryHash) -> ExternResult<Vec<Entity<Comm
 }
 
 
+#[hdk_extern]
+pub fn get_comment_collection_for_post(post_id: EntryHash) -> ExternResult<EntityCollection<CommentEntry>> {
+    Ok( get_entity_collection( &post_id, LinkTypes::Comment, None )? )
+}
+
+
 #[hdk_extern]
 pub fn update_comment(mut input: UpdateEntityInput<CommentEntry>) -> ExternResult<Entity<CommentEntry>> {
     if input.properties.last_updated.is_none() {